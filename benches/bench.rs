@@ -0,0 +1,35 @@
+use criterion::{criterion_group, criterion_main};
+
+#[maybe_async::both]
+async fn sum(numbers: &[u64]) -> u64 {
+    numbers.iter().sum()
+}
+
+#[maybe_async::bench(async = tokio, fns(sum))]
+async fn sum_tokio() -> u64 {
+    sum(&[1, 2, 3]).await
+}
+
+#[maybe_async::bench(async = async_std, fns(sum))]
+async fn sum_async_std() -> u64 {
+    sum(&[1, 2, 3]).await
+}
+
+#[maybe_async::bench(async(smol), fns(sum))]
+async fn sum_smol() -> u64 {
+    sum(&[1, 2, 3]).await
+}
+
+#[maybe_async::bench(async = criterion::async_executor::FuturesExecutor, fns(sum))]
+async fn sum_futures_executor() -> u64 {
+    sum(&[1, 2, 3]).await
+}
+
+criterion_group!(
+    benches,
+    sum_tokio,
+    sum_async_std,
+    sum_smol,
+    sum_futures_executor
+);
+criterion_main!(benches);
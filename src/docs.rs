@@ -0,0 +1,462 @@
+use std::env;
+
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::{
+    visit_mut::{self, VisitMut},
+    Expr, File, Ident, Item, ItemFn, Lit, Macro, MetaNameValue, Signature, UseName, UseRename,
+};
+
+use crate::{
+    lint,
+    parse::Args,
+    visit::{AsyncAwaitRemoval, RemoveAsyncAttrs, RenameTypes},
+};
+
+/// Words of the info string of a code block which rustdoc runs as Rust
+const RUST_ATTRS: &[&str] = &[
+    "rust",
+    "ignore",
+    "should_panic",
+    "no_run",
+    "compile_fail",
+    "test_harness",
+    "standalone_crate",
+];
+
+/// Convert the Rust code blocks in the doc comments of an item to one
+/// version, like the item itself: `.await` and async runtime attributes are
+/// removed in the sync version, and the item and the dual mode types are
+/// renamed with the suffix of the version
+pub struct DocExamples<'a> {
+    is_sync: bool,
+    /// Dual mode types, renamed like `Client` to `ClientSync`
+    types: &'a [String],
+    /// The converted function, renamed like `fetch` to `fetch_sync`
+    function: Vec<String>,
+    args: &'a Args,
+}
+
+impl<'a> DocExamples<'a> {
+    pub fn new(
+        is_sync: bool,
+        types: &'a [String],
+        function: Option<&Ident>,
+        args: &'a Args,
+    ) -> Self {
+        Self {
+            is_sync,
+            types,
+            function: function.map(ToString::to_string).into_iter().collect(),
+            args,
+        }
+    }
+
+    /// Convert the examples of the `#[doc = "..."]` attributes in `tokens`,
+    /// also in nested items
+    pub fn convert(&self, tokens: TokenStream) -> TokenStream {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        let mut output = TokenStream::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            // a doc comment is one attribute per line
+            let start = index;
+            let mut lines = vec![];
+            while let Some(line) = doc_line(&tokens[index..]) {
+                lines.push(line);
+                index += 2;
+            }
+            if !lines.is_empty() {
+                match self.convert_lines(&lines) {
+                    Some(lines) => {
+                        let span = tokens[start].span();
+                        output.extend(
+                            lines
+                                .iter()
+                                .map(|line| quote_spanned!(span=> #[doc = #line])),
+                        );
+                    }
+                    None => output.extend(tokens[start..index].iter().cloned()),
+                }
+                continue;
+            }
+            match &tokens[index] {
+                TokenTree::Group(group) => {
+                    let mut converted = Group::new(group.delimiter(), self.convert(group.stream()));
+                    converted.set_span(group.span());
+                    output.extend([TokenTree::Group(converted)]);
+                }
+                token => output.extend([token.clone()]),
+            }
+            index += 1;
+        }
+        output
+    }
+
+    /// The lines of a doc comment with its Rust code blocks converted, `None`
+    /// if nothing changed
+    fn convert_lines(&self, lines: &[String]) -> Option<Vec<String>> {
+        let mut output = vec![];
+        let mut changed = false;
+        let mut index = 0;
+        while index < lines.len() {
+            let line = &lines[index];
+            output.push(line.clone());
+            index += 1;
+            let Some((fence, info)) = code_fence(line) else {
+                continue;
+            };
+            let Some(len) = lines[index..]
+                .iter()
+                .position(|line| is_closing_fence(line, fence))
+            else {
+                // an unclosed block runs to the end of the comment
+                output.extend(lines[index..].iter().cloned());
+                break;
+            };
+            let code = lines[index..index + len].join("\n");
+            let converted = if is_rust(info) {
+                self.convert_code(&code)
+            } else {
+                None
+            };
+            match converted {
+                Some(converted) => {
+                    output.extend(converted.split('\n').map(str::to_string));
+                    changed = true;
+                }
+                None => output.extend(lines[index..index + len].iter().cloned()),
+            }
+            output.push(lines[index + len].clone());
+            index += len + 1;
+        }
+        changed.then_some(output)
+    }
+
+    /// Convert an example, re-emitted one item or statement per line from the
+    /// converted syntax tree. `None` if it does not parse or needs no change
+    fn convert_code(&self, code: &str) -> Option<String> {
+        // rustdoc hides lines starting with `# `, which are still compiled
+        let mut visible = vec![];
+        let mut hidden = String::new();
+        for line in code.split('\n') {
+            let trimmed = line.trim_start();
+            if trimmed == "#" || trimmed.starts_with("# ") {
+                hidden.push_str(&trimmed[1..]);
+                visible.push(trimmed[1..].to_string());
+            } else {
+                visible.push(line.to_string());
+            }
+        }
+        let visible = visible.join("\n");
+
+        // examples are items, or statements rustdoc wraps in `fn main`
+        let (mut file, wrapped) = match syn::parse_str::<File>(&visible) {
+            Ok(file) => (file, false),
+            Err(_) => (
+                syn::parse_str::<File>(&format!("fn main() {{\n{}\n}}", visible)).ok()?,
+                true,
+            ),
+        };
+        let original = file.clone();
+        let (type_suffix, fn_suffix) = if self.is_sync {
+            ("Sync", "_sync")
+        } else {
+            ("Async", "_async")
+        };
+        // examples use the items by the name of the crate
+        let crate_name = env::var("CARGO_CRATE_NAME").ok();
+        RenameTypes::new(self.types, type_suffix)
+            .with_crate_name(crate_name.clone())
+            .visit_file_mut(&mut file);
+        RenameTypes::new(&self.function, fn_suffix)
+            .with_crate_name(crate_name)
+            .visit_file_mut(&mut file);
+        RenameNames {
+            types: (self.types, type_suffix),
+            function: (&self.function, fn_suffix),
+        }
+        .visit_file_mut(&mut file);
+        if self.is_sync {
+            // async only APIs make no sense in the sync version, keep the
+            // example as it is
+            if !lint::async_only_apis(&file).is_empty() {
+                return None;
+            }
+            RemoveAsyncAttrs::new(&self.args.async_only_attrs).visit_file_mut(&mut file);
+            RemoveAsyncness.visit_file_mut(&mut file);
+            AsyncAwaitRemoval::new(&self.args.macros).visit_file_mut(&mut file);
+        }
+        if quote!(#file).to_string() == quote!(#original).to_string() {
+            return None;
+        }
+
+        // a function like `main` written under hidden lines keeps them, with
+        // its statements shown
+        let hidden = without_whitespace(&hidden);
+        let open = original
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Fn(item) if !wrapped => {
+                    hidden.contains(&without_whitespace(&fn_header(item)))
+                }
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        let mut cursor = 0;
+        let output = lines(&original, wrapped, &open)
+            .iter()
+            .zip(lines(&file, wrapped, &open))
+            .map(|(original, converted)| {
+                // the lines of the example are in the order of the hidden text
+                match hidden[cursor..].find(&without_whitespace(original)) {
+                    Some(at) if !original.is_empty() => {
+                        cursor += at + without_whitespace(original).len();
+                        format!("# {}", converted)
+                    }
+                    _ => converted,
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(output.join("\n"))
+    }
+}
+
+/// The lines of a parsed example: its items, the statements of `main` when
+/// rustdoc wraps it, and the statements of the functions in `open` between
+/// their signature and closing brace
+fn lines(file: &File, wrapped: bool, open: &[bool]) -> Vec<String> {
+    let mut lines = vec![];
+    if wrapped {
+        if let Some(Item::Fn(main)) = file.items.first() {
+            lines.extend(
+                main.block
+                    .stmts
+                    .iter()
+                    .map(|stmt| quote!(#stmt).to_string()),
+            );
+        }
+        return lines;
+    }
+    lines.extend(file.attrs.iter().map(|attr| quote!(#attr).to_string()));
+    for (item, open) in file.items.iter().zip(open) {
+        match item {
+            Item::Fn(item) if *open => {
+                lines.push(fn_header(item));
+                lines.extend(
+                    item.block
+                        .stmts
+                        .iter()
+                        .map(|stmt| quote!(#stmt).to_string()),
+                );
+                lines.push("}".to_string());
+            }
+            item => lines.push(quote!(#item).to_string()),
+        }
+    }
+    lines
+}
+
+/// A function up to the opening brace of its body
+fn fn_header(item: &ItemFn) -> String {
+    let ItemFn {
+        attrs, vis, sig, ..
+    } = item;
+    format!("{} {{", quote!(#(#attrs)* #vis #sig))
+}
+
+fn without_whitespace(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// The text of a `#[doc = "..."]` attribute at the start of `tokens`
+fn doc_line(tokens: &[TokenTree]) -> Option<String> {
+    let [TokenTree::Punct(pound), TokenTree::Group(group), ..] = tokens else {
+        return None;
+    };
+    if pound.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+        return None;
+    }
+    let meta = syn::parse2::<MetaNameValue>(group.stream()).ok()?;
+    match meta.value {
+        Expr::Lit(lit) if meta.path.is_ident("doc") => match lit.lit {
+            Lit::Str(lit) => Some(lit.value()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The fence and the info string of a line opening a code block
+fn code_fence(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    (len >= 3).then(|| (&trimmed[..len], trimmed[len..].trim()))
+}
+
+/// Whether `line` closes a code block opened by `fence`
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with(fence) && trimmed.chars().all(|c| fence.starts_with(c))
+}
+
+/// Whether rustdoc runs a code block with the info string `info` as Rust
+fn is_rust(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .all(|word| {
+            RUST_ATTRS.contains(&word)
+                || word.starts_with("edition")
+                || word.starts_with("ignore-")
+                || (word.starts_with('E') && word[1..].chars().all(|c| c.is_ascii_digit()))
+        })
+        || info.split(',').any(|word| word.trim() == "rust")
+}
+
+/// Rename dual mode types and the converted function where `RenameTypes`
+/// does not look, in imports like `use my_crate::Client` and in the
+/// arguments of macros like `assert_eq!`
+struct RenameNames<'a> {
+    types: (&'a [String], &'a str),
+    function: (&'a [String], &'a str),
+}
+
+impl RenameNames<'_> {
+    fn rename(&self, ident: &mut Ident) {
+        for (names, suffix) in [self.types, self.function] {
+            if names.iter().any(|name| ident == name) {
+                *ident = Ident::new(&format!("{}{}", ident, suffix), ident.span());
+                return;
+            }
+        }
+    }
+}
+
+impl VisitMut for RenameNames<'_> {
+    fn visit_use_name_mut(&mut self, i: &mut UseName) {
+        self.rename(&mut i.ident);
+    }
+
+    fn visit_use_rename_mut(&mut self, i: &mut UseRename) {
+        self.rename(&mut i.ident);
+    }
+
+    fn visit_macro_mut(&mut self, i: &mut Macro) {
+        i.tokens = self.rename_tokens(i.tokens.clone());
+    }
+}
+
+impl RenameNames<'_> {
+    /// Rename the idents of `tokens` which are not a method or a field
+    fn rename_tokens(&self, tokens: TokenStream) -> TokenStream {
+        let mut after_dot = false;
+        tokens
+            .into_iter()
+            .map(|token| {
+                let token = match token {
+                    TokenTree::Group(group) => {
+                        let mut renamed =
+                            Group::new(group.delimiter(), self.rename_tokens(group.stream()));
+                        renamed.set_span(group.span());
+                        TokenTree::Group(renamed)
+                    }
+                    TokenTree::Ident(mut ident) if !after_dot => {
+                        self.rename(&mut ident);
+                        TokenTree::Ident(ident)
+                    }
+                    token => token,
+                };
+                after_dot = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '.');
+                token
+            })
+            .collect()
+    }
+}
+
+/// Remove `async` from the functions of a sync example, like
+/// `async fn main()`
+struct RemoveAsyncness;
+
+impl VisitMut for RemoveAsyncness {
+    fn visit_signature_mut(&mut self, i: &mut Signature) {
+        i.asyncness = None;
+        visit_mut::visit_signature_mut(self, i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Convert the code block `code` to the sync version, with `Client` as a
+    /// dual mode type and `fetch` as the converted function
+    fn convert_sync(code: &str) -> Option<String> {
+        let args = Args::default();
+        let types = ["Client".to_string()];
+        let function = Ident::new("fetch", proc_macro2::Span::call_site());
+        let lines = format!("```\n{}\n```", code)
+            .split('\n')
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let converted = DocExamples::new(true, &types, Some(&function), &args)
+            .convert_lines(&lines)?
+            .join("\n");
+        let code = converted.strip_prefix("```\n")?.strip_suffix("\n```")?;
+        Some(code.to_string())
+    }
+
+    #[test]
+    fn test_convert_use_and_hidden_main() {
+        let code = "use my_crate::Client;\n\
+                    # #[tokio::main]\n\
+                    # async fn main() -> Result<(), String> {\n\
+                    let body = fetch(\"url\").await?;\n\
+                    Client::new().send(body).await;\n\
+                    # Ok(())\n\
+                    # }";
+        assert_eq!(
+            convert_sync(code).unwrap(),
+            "use my_crate :: ClientSync ;\n\
+             # fn main () -> Result < () , String > {\n\
+             let body = fetch_sync (\"url\") ? ;\n\
+             ClientSync :: new () . send (body) ;\n\
+             # Ok (())\n\
+             # }"
+        );
+    }
+
+    #[test]
+    fn test_convert_statements() {
+        // rustdoc wraps statements in `fn main`, strings are left as they are
+        let code = "# let url = \"ping.await\";\n\
+                    let reply = fetch(url)\n    .await;";
+        assert_eq!(
+            convert_sync(code).unwrap(),
+            "# let url = \"ping.await\" ;\n\
+             let reply = fetch_sync (url) ;"
+        );
+    }
+
+    #[test]
+    fn test_convert_unchanged() {
+        assert_eq!(convert_sync("let n = 1 + 1;\nassert_eq!(n, 2);"), None);
+        assert_eq!(convert_sync("not rust {"), None);
+    }
+
+    #[test]
+    fn test_convert_keeps_other_blocks() {
+        let args = Args::default();
+        let examples = DocExamples::new(true, &[], None, &args);
+        for info in &["text", "sh", "toml"] {
+            let lines = [
+                format!("```{}", info),
+                "fetch(url).await".into(),
+                "```".into(),
+            ];
+            assert_eq!(examples.convert_lines(&lines), None);
+        }
+        assert_eq!(examples.convert_lines(&["Text.".to_string()]), None);
+    }
+}
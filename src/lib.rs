@@ -123,6 +123,14 @@
 //!     methods, invoke the maybe_async macro as #[async_impl(?Send)].
 //!
 //!
+//! - `both`
+//!
+//!     **Keep both versions in one build**. The item is converted once per
+//!     enabled version, with the suffix of the version: `Client` becomes
+//!     `ClientSync` and `ClientAsync`, `fetch` becomes `fetch_sync` and
+//!     `fetch_async`. Enable both `is_sync` and `is_async` to get both. See
+//!     [Both Versions in One Build](#both-versions-in-one-build).
+//!
 //! - `test`
 //!
 //!     Handy macro to unify async and sync **unit and e2e test** code.
@@ -150,6 +158,176 @@
 //!     }
 //!     ```
 //!
+//!     With `both` as the first argument, the test runs once per enabled
+//!     version, see [Tests and benchmarks](#tests-and-benchmarks).
+//!
+//! ## Both Versions in One Build
+//!
+//! A crate offering a blocking and an async client side by side enables both
+//! `is_sync` and `is_async`, and marks its items with `both`. Each item is
+//! emitted once per enabled version, under the name of the version.
+//!
+//! ```rust
+//! #[maybe_async::both]
+//! pub struct Client {
+//!     host: String,
+//! }
+//!
+//! #[maybe_async::both]
+//! impl Client {
+//!     pub async fn ping(&self) -> bool {
+//!         !self.host.is_empty()
+//!     }
+//! }
+//! ```
+//!
+//! `both` accepts `?Send` for the generated `#[async_trait]`, and the
+//! arguments below. The markers and the lints also work in the other
+//! conversion macros.
+//!
+//! ### Conversion
+//!
+//! `await` inside macro invocations is removed in the sync version. Arguments
+//! of std macros like `assert_eq!`, `println!` or `format!` are converted as
+//! expressions, other macros with expression arguments can be added with
+//! `macros(...)`, e.g. `#[maybe_async::both(?Send, macros(tracing::info))]`.
+//! `.await` is simply stripped from the tokens of all other macros.
+//!
+//! Attribute macros of async runtimes, like `#[async_trait]`,
+//! `#[tokio::main]`, `#[tokio::test]`, `#[async_std::main]` or
+//! `#[async_recursion]`, are removed from the sync version. More can be added
+//! with `async_only_attrs(tracing::instrument)`. An `#[async_trait]` written on
+//! a trait or an impl is used in the async version instead of the generated
+//! one.
+//!
+//! Async only APIs like `tokio::spawn`, `tokio::select!` or
+//! `tokio::time::timeout` have no meaning in sync code, and are reported as
+//! compile errors at their original span. With `blocking`, the async version
+//! is checked for blocking APIs like `std::thread::sleep`, `std::fs::read` or
+//! `reqwest::blocking`, which stall the executor. More APIs can be listed with
+//! `blocking(my_crate::block_on)`. They are reported as deprecation warnings,
+//! or as compile errors with `strict`.
+//!
+//! Nested trait items, impl items, struct fields, enum variants and
+//! statements marked `#[sync_only]` or `#[async_only]` are only kept in that
+//! version, e.g. an `#[async_only] fn into_stream` next to a
+//! `#[sync_only] fn into_iter`.
+//!
+//! Fields, enum variant payloads and type aliases can have a type per version
+//! with `#[sync_type(..)]` and `#[async_type(..)]`, e.g.
+//! `#[sync_type(reqwest::blocking::Client)] #[async_type(reqwest::Client)] http: _`.
+//! Attributes of one version only are written like `cfg_attr` with
+//! `#[sync_attr(..)]` and `#[async_attr(..)]`, e.g.
+//! `#[sync_attr(derive(Clone))]`, on the item itself and on nested items,
+//! methods, fields and variants.
+//!
+//! Small differences in code can be written with
+//! `select_mode!{ sync => ..., async => ... }`, replaced with the arm of the
+//! version, and `is_async!()` tells the version. Converted structs and enums
+//! also have an `IS_ASYNC` associated const.
+//!
+//! ### Dual mode types
+//!
+//! References to the item itself are renamed with the suffix of the version.
+//! Other dual mode types referenced in fields, signatures, bounds,
+//! supertraits, `dyn` and the trait of an impl are renamed too when listed
+//! with `types(Inner, Transport)`, or registered for the whole crate in
+//! `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.maybe-async]
+//! types = ["Inner", "Transport"]
+//! ```
+//!
+//! Plain data types like `Config` or `Error` are often the same in both
+//! versions. `#[maybe_async::both(shared)]` emits such an item once under its
+//! original name, and fails if the versions would differ. References to
+//! shared items are left as they are.
+//!
+//! ### Docs
+//!
+//! Rust code blocks in the doc comments are converted with each version, so
+//! the examples of `ClientSync` and `ClientAsync` compile on their own. The
+//! item and the dual mode types are renamed, also in `use` and in macro
+//! arguments, and the sync version loses `.await`, `async` and runtime
+//! attributes like `#[tokio::main]`. A converted example is written again
+//! from its syntax tree, one item or statement per line, without its comments
+//! and formatting; hidden `# ` lines stay hidden. Examples which need no
+//! change, use async only APIs or do not parse are left as they are.
+//!
+//! Docs of one version are written with `#[doc_sync = "..."]` and
+//! `#[doc_async = "..."]`, like `#[doc = "..."]`. When both versions are
+//! enabled, their docs link to each other. Each version is marked with the
+//! feature of the crate enabling it for docs.rs, like `blocking` for
+//! `blocking = ["maybe-async/is_sync"]` in `[features]`, by
+//! `#[cfg_attr(docsrs, doc(cfg(...)))]`. The crate opts in with
+//! `#![cfg_attr(docsrs, feature(doc_cfg))]`.
+//!
+//! ### Tests and benchmarks
+//!
+//! `#[maybe_async::test(both, async = tokio::test)]` generates the test once
+//! per enabled version, as `test_x_sync` under `#[test]` and `test_x_async`
+//! under the macro passed by `async = ...`. Awaited calls of the dual mode
+//! functions listed with `fns(fetch)`, like `fetch(url).await` or
+//! `crate::api::fetch(url).await`, are renamed to `fetch_sync` and
+//! `fetch_async`, other calls keep their names, and dual mode types are
+//! renamed like in `both`. Put runtime specific calls in `select_mode!`.
+//!
+//! ```rust
+//! #[maybe_async::both]
+//! async fn async_fn() -> bool {
+//!     true
+//! }
+//!
+//! #[maybe_async::test(both, async(smol), timeout = "5s", fns(async_fn))]
+//! async fn test_async_fn() {
+//!     assert_eq!(async_fn().await, true);
+//! }
+//! ```
+//!
+//! Runtimes have shorthands in place of the test macro: `tokio`,
+//! `tokio(flavor = "multi_thread", worker_threads = 2)`, `async_std`, and
+//! `smol` or `futures_executor`, which run the test with their `block_on`.
+//! They also work with conditions, where `async(runtime)` alone runs when the
+//! sync condition is not met, and conditions met at the same time are
+//! reported as compile errors.
+//!
+//! - `timeout = "5s"` fails a test running longer, by the timeout of the
+//!   runtime in the async version. The sync version stays on the test thread,
+//!   a timer thread reports it at the deadline and it fails once done.
+//!   Durations are written in `ms`, `s` or `m`.
+//! - `matrix(tokio, async_std, smol)` runs the async version on every listed
+//!   runtime in the same build, as `test_x__tokio`, `test_x__async_std` and so
+//!   on.
+//! - The sync version runs under `#[test]`, another harness macro is passed
+//!   by `sync = test_log::test`, or `sync(condition, test_log::test)` in place
+//!   of the sync condition.
+//! - Attributes written below `maybe_async::test`, like `#[should_panic]`,
+//!   `#[ignore]` or `#[serial_test::serial]`, are kept in that order after the
+//!   harness macro of each version. Tests may return a `Result`.
+//! - Like `rstest`, a test taking parameters is run once per `#[case(...)]`
+//!   with the arguments of the case, as `test_x_case_1`, `test_x_case_2` and
+//!   so on, or `test_x_empty` for `#[case::empty(...)]`, and then the suffix
+//!   of each version.
+//!
+//! `#[maybe_async::bench(async = tokio)]` turns an async function into a
+//! criterion benchmark of the same name, running it in each enabled version
+//! as the `sync` and `async` functions of one benchmark group. The async
+//! version runs on `tokio`, `async_std`, `smol`, `futures_executor`, or an
+//! expression of a criterion `AsyncExecutor`, with the matching `async_*`
+//! feature of criterion enabled. `fns(...)` and `types(...)` work like in
+//! tests.
+//!
+//! ### Renamed dependency
+//!
+//! The markers and macros of this crate are recognized bare, like
+//! `#[sync_only]`, or under the name of the dependency in `Cargo.toml`, so a
+//! renamed one like `mba = { package = "maybe-async", ... }` works as
+//! `#[mba::sync_only]`. The same goes for `async-trait` in the generated
+//! `#[async_trait]`. A crate re-exporting them passes their paths with
+//! `crate = my_facade::maybe_async` and `async_trait = my_facade::async_trait`,
+//! also accepted by `test` and `bench`.
+//!
 //! ## What's Under the Hook
 //!
 //! `maybe-async` compiles your code in different way with the `is_sync` feature
@@ -281,15 +459,23 @@ use proc_macro::TokenStream;
 
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use syn::{
-    parse_macro_input, spanned::Spanned, AttributeArgs, Ident, ImplItem, ItemImpl, Lit, Meta,
-    NestedMeta, Path, TraitItem, Type, TypePath,
+    parse_macro_input, parse_quote, spanned::Spanned, Generics, Ident, ImplItem, ItemFn, Lit, Meta,
+    Path, TraitItem, Type, TypePath, Visibility,
 };
 
 use quote::quote;
 
-use crate::{parse::Item, visit::AsyncAwaitRemoval};
+use crate::{
+    docs::DocExamples,
+    parse::{Args, AttributeArgs, Item, NestedMeta},
+    visit::{dedup_async_trait, AsyncAwaitRemoval, RemoveAsyncAttrs, RenameTypes, SelectMode},
+};
 
+mod docs;
+mod lint;
+mod manifest;
 mod parse;
+mod testing;
 mod visit;
 
 fn ident_add_suffix(ident: &Ident, suffix: &str) -> Ident {
@@ -297,36 +483,157 @@ fn ident_add_suffix(ident: &Ident, suffix: &str) -> Ident {
     Ident::new(&format!("{}{}", ident, suffix), ident.span())
 }
 
-// Appends a suffix to the last segment in the impl's path
-fn impl_add_suffix(input: &mut ItemImpl, suffix: &str) {
-    if let Type::Path(TypePath {
-        path: Path { segments, .. },
-        ..
-    }) = &mut *input.self_ty
-    {
-        if let Some(last) = segments.last_mut() {
-            last.ident = ident_add_suffix(&last.ident, suffix);
-        }
+// Names of the other dual mode types, which are renamed with the suffix of
+// the version: the registered ones and the listed ones
+fn dual_mode_types(args: &Args) -> syn::Result<Vec<String>> {
+    let mut types =
+        manifest::registered_types().map_err(|msg| syn::Error::new(Span::call_site(), msg))?;
+    types.extend(args.types.iter().map(ToString::to_string));
+    Ok(types)
+}
+
+// Cargo.toml as an input of the crate, so editing the registered types
+// rebuilds it. Emitted next to the types, which are `both` items.
+fn manifest_input() -> TokenStream2 {
+    if manifest::path().is_none() {
+        return TokenStream2::new();
+    }
+    quote!(
+        const _: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+    )
+}
+
+// The path of a dependency in the generated code: the one passed, else its
+// name in Cargo.toml, which may be renamed like `mba = { package = "maybe-async" }`
+fn dependency_path(path: Option<&Path>, package: &str) -> Path {
+    path.cloned().unwrap_or_else(|| {
+        let name = manifest::dependency_name(package).unwrap_or_else(|| package.replace('-', "_"));
+        Ident::new(&name, Span::call_site()).into()
+    })
+}
+
+// The path of this crate in the generated code, `crate = path` or detected
+fn crate_path(args: &Args) -> Path {
+    dependency_path(args.crate_path.as_ref(), "maybe-async")
+}
+
+// The name of the type `input` defines or implements, references to it are
+// renamed like the other dual mode types
+fn own_type(input: &Item) -> Option<&Ident> {
+    match input {
+        Item::Trait(item) => Some(&item.ident),
+        Item::Struct(item) => Some(&item.ident),
+        Item::Enum(item) => Some(&item.ident),
+        Item::Type(item) => Some(&item.ident),
+        Item::Impl(item) => match &*item.self_ty {
+            Type::Path(TypePath { path, .. }) => path.segments.last().map(|last| &last.ident),
+            _ => None,
+        },
+        Item::Fn(_) => None,
     }
+}
 
-    // TODO: Only `impl X` blocks are supported for now, not with traits
-    // if let Some((_, Path { segments, .. }, _)) = &mut input.trait_ {
-    //     if let Some(last) = segments.last_mut() {
-    //         last.ident = ident_add_suffix(&last.ident, suffix);
-    //     }
-    // }
+// The name of the function `input` defines, renamed in the examples of the
+// docs like the function itself
+fn own_fn(input: &Item) -> Option<Ident> {
+    match input {
+        Item::Fn(item) => Some(item.sig.ident.clone()),
+        _ => None,
+    }
 }
 
-fn convert_async(mut input: Item, send: bool) -> TokenStream2 {
-    let prefix = match (send, &input) {
-        (true, Item::Impl(_) | Item::Trait(_)) => quote!(#[async_trait::async_trait]),
-        (false, Item::Impl(_) | Item::Trait(_)) => quote!(#[async_trait::async_trait(?Send)]),
+// Whether `input` is the same in both versions apart from its name, so a
+// single copy under the original name serves both, checked for `shared` items
+fn is_shareable(input: &Item, args: &Args) -> bool {
+    let has_async_fn =
+        match input {
+            Item::Fn(item) => item.sig.asyncness.is_some(),
+            Item::Impl(item) => item.items.iter().any(
+                |inner| matches!(inner, ImplItem::Fn(method) if method.sig.asyncness.is_some()),
+            ),
+            Item::Trait(item) => item.items.iter().any(
+                |inner| matches!(inner, TraitItem::Fn(method) if method.sig.asyncness.is_some()),
+            ),
+            Item::Struct(_) | Item::Enum(_) | Item::Type(_) => false,
+        };
+    let Ok(types) = dual_mode_types(args) else {
+        return false;
+    };
+    let krate = crate_path(args);
+    let original = quote!(#input).to_string();
+    !has_async_fn
+        && [(true, "Sync"), (false, "Async")]
+            .iter()
+            .all(|&(is_sync, suffix)| {
+                let mut item = input.clone();
+                if !SelectMode::new(is_sync, &krate)
+                    .select(&mut item)
+                    .is_empty()
+                {
+                    return false;
+                }
+                if is_sync {
+                    RemoveAsyncAttrs::new(&args.async_only_attrs).remove(&mut item);
+                }
+                item.visit_mut(&mut RenameTypes::new(&types, suffix));
+                let converted = if is_sync {
+                    AsyncAwaitRemoval::new(&args.macros).remove_async_await(quote!(#item))
+                } else {
+                    quote!(#item)
+                };
+                converted.to_string() == original
+            })
+}
+
+// Generates the `IS_ASYNC` associated const of a converted struct or enum.
+// Traits have none, it would make them dyn incompatible.
+fn is_async_const(
+    ident: &Ident,
+    generics: &Generics,
+    vis: &Visibility,
+    is_async: bool,
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote!(
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Whether this is the async version of the type
+            #vis const IS_ASYNC: bool = #is_async;
+        }
+    )
+}
+
+fn convert_async(mut input: Item, args: &Args) -> TokenStream2 {
+    let mut errors = SelectMode::new(false, &crate_path(args)).select(&mut input);
+    let mut types = match dual_mode_types(args) {
+        Ok(types) => types,
+        Err(err) => return err.to_compile_error(),
+    };
+    types.extend(own_type(&input).map(ToString::to_string));
+    input.visit_mut(&mut RenameTypes::new(&types, "Async"));
+    let own_fn = own_fn(&input);
+    let docs = DocExamples::new(false, &types, own_fn.as_ref(), args);
+
+    if let Some(denylist) = &args.blocking {
+        errors.extend(lint::blocking_apis(&mut input, denylist, args.strict));
+    }
+    let errors = errors.into_iter().map(|error| error.to_compile_error());
+
+    // an `#[async_trait]` written on the item takes the place of ours
+    let has_async_trait = match &mut input {
+        Item::Impl(item) => dedup_async_trait(&mut item.attrs),
+        Item::Trait(item) => dedup_async_trait(&mut item.attrs),
+        _ => false,
+    };
+    let async_trait = dependency_path(args.async_trait.as_ref(), "async-trait");
+    let prefix = match (args.send, &input) {
+        _ if has_async_trait => quote!(),
+        (true, Item::Impl(_) | Item::Trait(_)) => quote!(#[#async_trait::async_trait]),
+        (false, Item::Impl(_) | Item::Trait(_)) => quote!(#[#async_trait::async_trait(?Send)]),
         _ => quote!(),
     };
 
-    match &mut input {
+    let output = match &mut input {
         Item::Impl(item) => {
-            impl_add_suffix(item, "Async");
             if item.trait_.is_none() {
                 quote!(#item)
             } else {
@@ -335,11 +642,13 @@ fn convert_async(mut input: Item, send: bool) -> TokenStream2 {
         }
         Item::Struct(item) => {
             item.ident = ident_add_suffix(&item.ident, "Async");
-            quote!(#item)
+            let is_async = is_async_const(&item.ident, &item.generics, &item.vis, true);
+            quote!(#item #is_async)
         }
         Item::Enum(item) => {
             item.ident = ident_add_suffix(&item.ident, "Async");
-            quote!(#item)
+            let is_async = is_async_const(&item.ident, &item.generics, &item.vis, true);
+            quote!(#item #is_async)
         }
         Item::Trait(item) => {
             item.ident = ident_add_suffix(&item.ident, "Async");
@@ -349,116 +658,198 @@ fn convert_async(mut input: Item, send: bool) -> TokenStream2 {
             item.sig.ident = ident_add_suffix(&item.sig.ident, "_async");
             quote!(#item)
         }
-    }
-    .into()
+        Item::Type(item) => {
+            item.ident = ident_add_suffix(&item.ident, "Async");
+            quote!(#item)
+        }
+    };
+    let output = docs.convert(output);
+    quote!(#output #(#errors)*)
 }
 
-fn convert_sync(mut input: Item) -> TokenStream2 {
-    match &mut input {
+fn convert_sync(mut input: Item, args: &Args) -> TokenStream2 {
+    let errors = SelectMode::new(true, &crate_path(args)).select(&mut input);
+    RemoveAsyncAttrs::new(&args.async_only_attrs).remove(&mut input);
+    let errors = errors.into_iter().map(|error| error.to_compile_error());
+    let mut types = match dual_mode_types(args) {
+        Ok(types) => types,
+        Err(err) => return err.to_compile_error(),
+    };
+    types.extend(own_type(&input).map(ToString::to_string));
+    input.visit_mut(&mut RenameTypes::new(&types, "Sync"));
+    let own_fn = own_fn(&input);
+    let docs = DocExamples::new(true, &types, own_fn.as_ref(), args);
+
+    let output = match &mut input {
         Item::Impl(item) => {
-            impl_add_suffix(item, "Sync");
             for inner in &mut item.items {
-                if let ImplItem::Method(ref mut method) = inner {
+                if let ImplItem::Fn(ref mut method) = inner {
                     if method.sig.asyncness.is_some() {
                         method.sig.asyncness = None;
                     }
                 }
             }
-            AsyncAwaitRemoval.remove_async_await(quote!(#item))
+            AsyncAwaitRemoval::new(&args.macros).remove_async_await(quote!(#item))
         }
         Item::Struct(item) => {
             item.ident = ident_add_suffix(&item.ident, "Sync");
-            quote!(#item)
+            let is_async = is_async_const(&item.ident, &item.generics, &item.vis, false);
+            quote!(#item #is_async)
         }
         Item::Enum(item) => {
             item.ident = ident_add_suffix(&item.ident, "Sync");
-            quote!(#item)
+            let is_async = is_async_const(&item.ident, &item.generics, &item.vis, false);
+            quote!(#item #is_async)
         }
         Item::Trait(item) => {
             item.ident = ident_add_suffix(&item.ident, "Sync");
             for inner in &mut item.items {
-                if let TraitItem::Method(ref mut method) = inner {
+                if let TraitItem::Fn(ref mut method) = inner {
                     if method.sig.asyncness.is_some() {
                         method.sig.asyncness = None;
                     }
                 }
             }
-            AsyncAwaitRemoval.remove_async_await(quote!(#item))
+            AsyncAwaitRemoval::new(&args.macros).remove_async_await(quote!(#item))
         }
         Item::Fn(item) => {
             item.sig.ident = ident_add_suffix(&item.sig.ident, "_sync");
             if item.sig.asyncness.is_some() {
                 item.sig.asyncness = None;
             }
-            AsyncAwaitRemoval.remove_async_await(quote!(#item))
+            AsyncAwaitRemoval::new(&args.macros).remove_async_await(quote!(#item))
         }
-    }
-    .into()
+        Item::Type(item) => {
+            item.ident = ident_add_suffix(&item.ident, "Sync");
+            quote!(#item)
+        }
+    };
+    let output = docs.convert(output);
+    quote!(#output #(#errors)*)
 }
 
 /// `maybe_async::both` attribute macro
 ///
-/// Can be applied to traits, trait impls, structs, struct impls and functions.
+/// Can be applied to traits, trait impls, structs, struct impls, functions and
+/// type aliases.
+///
+/// Emits the item converted to each enabled version, with the suffix of the
+/// version like `ClientSync` and `ClientAsync`. Accepts `?Send`,
+/// `types(...)`, `shared`, `macros(...)`, `async_only_attrs(...)`,
+/// `blocking(...)`, `strict`, `crate = path` and `async_trait = path`, see
+/// [Both Versions in One Build](crate#both-versions-in-one-build).
 #[proc_macro_attribute]
 pub fn both(args: TokenStream, input: TokenStream) -> TokenStream {
-    let send = match args.to_string().replace(" ", "").as_str() {
-        "" | "Send" => true,
-        "?Send" => false,
-        _ => {
-            return syn::Error::new(Span::call_site(), "Only accepts `Send` or `?Send`")
-                .to_compile_error()
-                .into();
-        }
-    };
+    let args = parse_macro_input!(args as Args);
 
     let item = parse_macro_input!(input as Item);
 
     let mut token = TokenStream2::new();
 
-    if cfg!(all(feature = "is_sync", feature = "is_async")) {
+    // `shared` items are emitted once, unconverted
+    if args.shared && !is_shareable(&item, &args) {
+        return syn::Error::new(
+            Span::call_site(),
+            "`shared` item differs between the sync and async versions, remove `shared` to \
+             convert it",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // functions and type aliases may be associated items, where `const _` is
+    // not allowed
+    if !matches!(&item, Item::Fn(_) | Item::Type(_)) {
+        token.extend(manifest_input());
+    }
+
+    if args.shared {
+        if cfg!(any(feature = "is_sync", feature = "is_async")) {
+            token.extend(quote!(#item));
+        }
+    } else if cfg!(all(feature = "is_sync", feature = "is_async")) {
         // We need a `clone` if both are enabled
-        token.extend(convert_sync(item.clone()));
-        token.extend(convert_async(item, send));
+        let mut sync_item = item.clone();
+        link_twin(&mut sync_item, "_async", "Async");
+        doc_cfg(&mut sync_item, "is_sync");
+        token.extend(convert_sync(sync_item, &args));
+        let mut async_item = item;
+        link_twin(&mut async_item, "_sync", "Sync");
+        doc_cfg(&mut async_item, "is_async");
+        token.extend(convert_async(async_item, &args));
     } else if cfg!(feature = "is_sync") {
-        token.extend(convert_sync(item));
+        let mut item = item;
+        doc_cfg(&mut item, "is_sync");
+        token.extend(convert_sync(item, &args));
     } else if cfg!(feature = "is_async") {
-        token.extend(convert_async(item, send));
+        let mut item = item;
+        doc_cfg(&mut item, "is_async");
+        token.extend(convert_async(item, &args));
     }
     token.into()
 }
 
+// Add a link to the other version of `item` to its docs, named with the
+// suffixes of the other version
+fn link_twin(item: &mut Item, fn_suffix: &str, type_suffix: &str) {
+    let twin = match &*item {
+        Item::Fn(item) => ident_add_suffix(&item.sig.ident, fn_suffix),
+        Item::Impl(_) => return,
+        item => match own_type(item) {
+            Some(ident) => ident_add_suffix(ident, type_suffix),
+            None => return,
+        },
+    };
+    let link = format!(" See also: [`{}`]", twin);
+    item.attrs_mut()
+        .extend([parse_quote!(#[doc = ""]), parse_quote!(#[doc = #link])]);
+}
+
+// Tell docs.rs which feature of the crate enables the version of `item`, the
+// one enabling `version` of maybe_async, like `blocking = ["maybe-async/is_sync"]`
+fn doc_cfg(item: &mut Item, version: &str) {
+    if let Some(feature) = manifest::enabling_feature(version) {
+        item.attrs_mut()
+            .push(parse_quote!(#[cfg_attr(docsrs, doc(cfg(feature = #feature)))]));
+    }
+}
+
 /// convert marked async code to async code with `async-trait`
+///
+/// Accepts `blocking(...)` and `strict` to report blocking APIs, like
+/// [`both`].
 #[proc_macro_attribute]
 pub fn must_be_async(args: TokenStream, input: TokenStream) -> TokenStream {
-    let send = match args.to_string().replace(" ", "").as_str() {
-        "" | "Send" => true,
-        "?Send" => false,
-        _ => {
-            return syn::Error::new(Span::call_site(), "Only accepts `Send` or `?Send`")
-                .to_compile_error()
-                .into();
-        }
-    };
+    let args = parse_macro_input!(args as Args);
     let item = parse_macro_input!(input as Item);
-    convert_async(item, send).into()
+    convert_async(item, &args).into()
 }
 
 /// convert marked async code to sync code
+///
+/// Async only APIs like `tokio::spawn`, `tokio::select!` or
+/// `tokio::time::timeout` have no meaning in sync code, and are reported as
+/// compile errors at their original span. Attribute macros of async runtimes
+/// are removed, like in [`both`].
 #[proc_macro_attribute]
-pub fn must_be_sync(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn must_be_sync(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as Args);
     let item = parse_macro_input!(input as Item);
-    convert_sync(item).into()
+    convert_sync(item, &args).into()
 }
 
 /// mark sync implementation
 ///
 /// only compiled when `is_sync` feature gate is set.
-/// When `is_sync` is not set, marked code is removed.
+/// When `is_sync` is not set, marked code is removed. The feature enabling it
+/// is shown on docs.rs, like in [`both`].
 #[proc_macro_attribute]
-pub fn sync_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn sync_impl(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as Args);
     let token = if cfg!(feature = "is_sync") {
-        let item = parse_macro_input!(input as Item);
-        let input = convert_sync(item);
+        let mut item = parse_macro_input!(input as Item);
+        doc_cfg(&mut item, "is_sync");
+        let input = convert_sync(item, &args);
         quote!(#input)
     } else {
         quote!()
@@ -469,28 +860,94 @@ pub fn sync_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
 /// mark async implementation
 ///
 /// only compiled when `is_sync` feature gate is not set.
-/// When `is_sync` is set, marked code is removed.
+/// When `is_sync` is set, marked code is removed. The feature enabling it is
+/// shown on docs.rs, like in [`both`].
 #[proc_macro_attribute]
 pub fn async_impl(args: TokenStream, input: TokenStream) -> TokenStream {
-    let send = match args.to_string().replace(" ", "").as_str() {
-        "" | "Send" => true,
-        "?Send" => false,
-        _ => {
-            return syn::Error::new(Span::call_site(), "Only accepts `Send` or `?Send`")
-                .to_compile_error()
-                .into();
-        }
-    };
+    let args = parse_macro_input!(args as Args);
 
     let token = if cfg!(feature = "is_async") {
-        let item = parse_macro_input!(input as Item);
-        convert_async(item, send)
+        let mut item = parse_macro_input!(input as Item);
+        doc_cfg(&mut item, "is_async");
+        convert_async(item, &args)
     } else {
         quote!()
     };
     token.into()
 }
 
+/// choose code by version inside converted items
+///
+/// `select_mode!{ sync => std::thread::sleep(d), async => tokio::time::sleep(d).await }`
+/// is replaced with the arm of the version by `both` and the other conversion
+/// macros, so the other arm is never type checked. Arms in braces hold
+/// statements, or items inside a trait or an impl. A missing arm expands to
+/// nothing.
+///
+/// Used anywhere else, it is a compile error.
+#[proc_macro]
+pub fn select_mode(_input: TokenStream) -> TokenStream {
+    syn::Error::new(
+        Span::call_site(),
+        "`select_mode!` can only be used inside items converted by maybe_async, like \
+         `#[maybe_async::both]`",
+    )
+    .to_compile_error()
+    .into()
+}
+
+/// tell the version inside converted items
+///
+/// `maybe_async::is_async!()` is replaced with `true` in the async version and
+/// `false` in the sync version by `both` and the other conversion macros, also
+/// in the arguments of other macros like `format!`. Structs and enums get the
+/// same value as an `IS_ASYNC` associated const, e.g. `ClientSync::IS_ASYNC`.
+/// Traits do not, as an associated const would keep them from being used as
+/// `dyn Trait`; use `is_async!()` in a converted generic item instead.
+///
+/// Used anywhere else, it is a compile error.
+#[proc_macro]
+pub fn is_async(_input: TokenStream) -> TokenStream {
+    syn::Error::new(
+        Span::call_site(),
+        "`is_async!` can only be used inside items converted by maybe_async, like \
+         `#[maybe_async::both]`",
+    )
+    .to_compile_error()
+    .into()
+}
+
+/// Handy macro to benchmark sync and async code with criterion
+///
+/// The marked async function becomes a criterion benchmark running it in
+/// each enabled version, on the runtime passed by `async = ...`. Return the
+/// result of the workload so criterion keeps it from being optimized away.
+/// See [Tests and benchmarks](crate#tests-and-benchmarks).
+///
+/// ```rust,no_run
+/// #[maybe_async::both]
+/// async fn parse(s: &str) -> u8 {
+///     s.parse().unwrap()
+/// }
+///
+/// #[maybe_async::bench(async = tokio, fns(parse))]
+/// async fn parse_numbers() -> u8 {
+///     parse("42").await
+/// }
+///
+/// criterion::criterion_group!(benches, parse_numbers);
+/// criterion::criterion_main!(benches);
+/// ```
+#[proc_macro_attribute]
+pub fn bench(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args with AttributeArgs::parse_terminated);
+    let item = parse_macro_input!(input as Item);
+    match testing::bench(args, item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 macro_rules! match_nested_meta_to_str_lit {
     ($t:expr) => {
         match $t {
@@ -521,7 +978,10 @@ macro_rules! match_nested_meta_to_str_lit {
 /// as async and the lib to run async test, e.x. `async-std::test`,
 /// `tokio::test`, or any valid attribute macro.
 ///
-/// **ATTENTION**: do not write await inside a assert macro
+/// `await` inside macros like `assert_eq!` is removed in sync version as
+/// well. With `both` as the first argument, the test runs once per enabled
+/// version, with runtime shorthands, `fns(...)`, `timeout`, `matrix(...)` and
+/// `#[case(...)]`, see [Tests and benchmarks](crate#tests-and-benchmarks).
 ///
 /// - Examples
 ///
@@ -561,17 +1021,25 @@ macro_rules! match_nested_meta_to_str_lit {
 ///
 /// // convert to sync version when sync condition is met, keep in async version when corresponding
 /// // condition is met
-/// #[cfg_attr(feature = "is_sync", must_be_sync, test)]
-/// #[cfg_attr(
-///     all(not(feature = "is_sync"), feature = "async_std"),
-///     must_be_async,
-///     async_std::test
-/// )]
-/// #[cfg_attr(
-///     all(not(feature = "is_sync"), feature = "tokio"),
-///     must_be_async,
-///     tokio::test
-/// )]
+/// #[cfg(feature = "is_sync")]
+/// #[must_be_sync]
+/// #[test]
+/// async fn test_async_fn() {
+///     let res = async_fn().await;
+///     assert_eq!(res, true);
+/// }
+///
+/// #[cfg(all(not(feature = "is_sync"), feature = "async_std"))]
+/// #[must_be_async]
+/// #[async_std::test]
+/// async fn test_async_fn() {
+///     let res = async_fn().await;
+///     assert_eq!(res, true);
+/// }
+///
+/// #[cfg(all(not(feature = "is_sync"), feature = "tokio"))]
+/// #[must_be_async]
+/// #[tokio::test]
 /// async fn test_async_fn() {
 ///     let res = async_fn().await;
 ///     assert_eq!(res, true);
@@ -587,7 +1055,7 @@ macro_rules! match_nested_meta_to_str_lit {
 /// ```
 #[proc_macro_attribute]
 pub fn test(args: TokenStream, input: TokenStream) -> TokenStream {
-    let attr_args = parse_macro_input!(args as AttributeArgs);
+    let attr_args = parse_macro_input!(args with AttributeArgs::parse_terminated);
     let input = TokenStream2::from(input);
     if attr_args.len() < 1 {
         return syn::Error::new(
@@ -598,17 +1066,72 @@ pub fn test(args: TokenStream, input: TokenStream) -> TokenStream {
         .into();
     }
 
-    // The first attributes indicates sync condition
-    let sync_cond = match_nested_meta_to_str_lit!(attr_args.first().unwrap());
-    let mut ts = quote!(#[cfg_attr(#sync_cond, maybe_async::must_be_sync, test)]);
+    // `both` generates a test per version
+    if matches!(attr_args.first(), Some(NestedMeta::Meta(Meta::Path(path))) if path.is_ident("both"))
+    {
+        return match syn::parse2(input).and_then(|item| testing::both(attr_args, item)) {
+            Ok(tokens) => tokens.into(),
+            Err(err) => err.to_compile_error().into(),
+        };
+    }
+
+    // The first attributes indicates sync condition, and the test harness
+    // macro in the form of `sync(cond, harness_macro)`
+    let (sync_cond, sync_harness) = match attr_args.first().unwrap() {
+        NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("sync") => {
+            let nested = match list.parse_args_with(AttributeArgs::parse_terminated) {
+                Ok(nested) => nested,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            match nested.len() {
+                1 => (match_nested_meta_to_str_lit!(&nested[0]), quote!(test)),
+                2 => (
+                    match_nested_meta_to_str_lit!(&nested[0]),
+                    match_nested_meta_to_str_lit!(&nested[1]),
+                ),
+                _ => {
+                    let msg = format!(
+                        "Must pass one or two metas or string literals like `sync(condition, \
+                         harness_macro)`, you passed {} metas.",
+                        nested.len()
+                    );
+                    return syn::Error::new(list.span(), msg).to_compile_error().into();
+                }
+            }
+        }
+        sync_cond => (match_nested_meta_to_str_lit!(sync_cond), quote!(test)),
+    };
 
     // The rest attributes indicates async condition and async test macro
-    // only accepts in the forms of `async(cond, test_macro)`, but `cond` and
-    // `test_macro` can be either meta attributes or string literal
-    let mut async_token = Vec::new();
-    let mut async_conditions = Vec::new();
+    // only accepts in the forms of `async(cond, test_macro)` or
+    // `async(runtime)`, but `cond` and `test_macro` can be either meta
+    // attributes or string literal. `matrix(tokio, async_std)` runs the
+    // async version on each runtime, `timeout = "5s"` applies to all versions.
+    let mut async_tests = Vec::new();
+    let mut matrix = Vec::new();
+    let mut timeout = None;
+    let mut krate = None;
     for async_meta in attr_args.into_iter().skip(1) {
         match async_meta {
+            NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("crate") => {
+                let value = &meta.value;
+                match syn::parse2(quote!(#value)) {
+                    Ok(path) => krate = Some(path),
+                    Err(err) => return err.to_compile_error().into(),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("timeout") => {
+                match testing::Timeout::parse(&meta.value) {
+                    Ok(value) => timeout = Some(value),
+                    Err(err) => return err.to_compile_error().into(),
+                }
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("matrix") => {
+                match testing::Runtime::parse_matrix(&list) {
+                    Ok(runtimes) => matrix.extend(runtimes),
+                    Err(err) => return err.to_compile_error().into(),
+                }
+            }
             NestedMeta::Meta(meta) => match meta {
                 Meta::List(list) => {
                     let name = list.path.segments[0].ident.to_string();
@@ -620,22 +1143,29 @@ pub fn test(args: TokenStream, input: TokenStream) -> TokenStream {
                         .to_compile_error()
                         .into();
                     }
-                    if list.nested.len() == 2 {
-                        let async_cond =
-                            match_nested_meta_to_str_lit!(list.nested.first().unwrap());
-                        let async_test = match_nested_meta_to_str_lit!(list.nested.last().unwrap());
-                        let attr = quote!(
-                            #[cfg_attr(#async_cond, maybe_async::must_be_async, #async_test)]
-                        );
-                        async_conditions.push(async_cond);
-                        async_token.push(attr);
-                    } else {
-                        let msg = format!(
-                            "Must pass two metas or string literals like `async(condition, \
-                             async_test_macro)`, you passed {} metas.",
-                            list.nested.len()
-                        );
-                        return syn::Error::new(list.span(), msg).to_compile_error().into();
+                    let nested = match list.parse_args_with(AttributeArgs::parse_terminated) {
+                        Ok(nested) => nested,
+                        Err(err) => return err.to_compile_error().into(),
+                    };
+                    let (async_cond, runtime) = match nested.len() {
+                        // a runtime shorthand runs when the sync condition is not met
+                        1 => (quote!(not(#sync_cond)), nested.first().unwrap()),
+                        2 => (
+                            match_nested_meta_to_str_lit!(nested.first().unwrap()),
+                            nested.last().unwrap(),
+                        ),
+                        _ => {
+                            let msg = format!(
+                                "Must pass two metas or string literals like `async(condition, \
+                                 async_test_macro)`, you passed {} metas.",
+                                nested.len()
+                            );
+                            return syn::Error::new(list.span(), msg).to_compile_error().into();
+                        }
+                    };
+                    match testing::Runtime::parse(runtime) {
+                        Ok(runtime) => async_tests.push((async_cond, runtime)),
+                        Err(err) => return err.to_compile_error().into(),
                     }
                 }
                 _ => {
@@ -658,18 +1188,51 @@ pub fn test(args: TokenStream, input: TokenStream) -> TokenStream {
         };
     }
 
-    async_token.into_iter().for_each(|t| ts.extend(t));
-    ts.extend(quote!( #input ));
-    if !async_conditions.is_empty() {
-        quote! {
-            #[cfg(any(#sync_cond, #(#async_conditions),*))]
-            #ts
-        }
-    } else {
-        quote! {
+    let input: ItemFn = match syn::parse2(input) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let krate = dependency_path(krate.as_ref(), "maybe-async");
+    let async_conds: Vec<_> = async_tests.iter().map(|(cond, _)| cond.clone()).collect();
+    let mut ts = testing::overlapping_conditions(&input.sig.ident, &sync_cond, &async_conds);
+    let cases = match testing::cases(input) {
+        Ok(cases) => cases,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    for input in cases {
+        let sync_test = testing::sync_test(input.clone(), timeout.as_ref());
+        ts.extend(quote!(
             #[cfg(#sync_cond)]
-            #ts
+            #[#krate::must_be_sync(crate = #krate)]
+            #[#sync_harness]
+            #sync_test
+        ));
+        for (async_cond, runtime) in &async_tests {
+            match testing::async_test(input.clone(), runtime, timeout.as_ref()) {
+                Ok((attr, async_test)) => ts.extend(quote!(
+                    #[cfg(#async_cond)]
+                    #[#krate::must_be_async(crate = #krate)]
+                    #attr
+                    #async_test
+                )),
+                Err(err) => return err.to_compile_error().into(),
+            }
+        }
+        // every runtime of the matrix runs when the sync condition is not met
+        let matrix_cond = quote!(not(#sync_cond));
+        match testing::matrix_tests(
+            &input,
+            &matrix,
+            timeout.as_ref(),
+            Some(&matrix_cond),
+            &Args {
+                crate_path: Some(krate.clone()),
+                ..Args::default()
+            },
+        ) {
+            Ok(tests) => ts.extend(tests),
+            Err(err) => return err.to_compile_error().into(),
         }
     }
-    .into()
+    ts.into()
 }
@@ -0,0 +1,292 @@
+use quote::quote_spanned;
+use syn::{
+    parse_quote,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    Block, Error, Expr, ExprAwait, ExprCall, ExprPath, File, ImplItemFn, ItemFn, Macro, Path,
+    TraitItemFn,
+};
+
+use crate::parse::Item;
+
+const SPAWN: &str = "it spawns a task onto the async runtime";
+const SLEEP: &str = "it returns a future driven by the runtime timer, use `std::thread::sleep` in \
+                     sync code";
+const TIMER: &str = "it returns a ticker driven by the runtime timer";
+const TIMEOUT: &str = "it cancels a future at a deadline, and a blocking call can not be cancelled";
+const SELECT: &str = "it polls futures concurrently and drops the unfinished ones";
+
+/// Async only functions, which have no meaning in sync code, and why
+const ASYNC_ONLY_FNS: &[(&str, &str)] = &[
+    ("tokio::spawn", SPAWN),
+    ("tokio::task::spawn", SPAWN),
+    ("tokio::task::spawn_local", SPAWN),
+    ("tokio::time::sleep", SLEEP),
+    ("tokio::time::sleep_until", SLEEP),
+    ("tokio::time::interval", TIMER),
+    ("tokio::time::timeout", TIMEOUT),
+    ("async_std::task::spawn", SPAWN),
+    ("async_std::task::spawn_local", SPAWN),
+    ("async_std::task::sleep", SLEEP),
+    ("async_std::future::timeout", TIMEOUT),
+    ("futures::future::select", SELECT),
+    ("futures::future::select_all", SELECT),
+];
+
+/// Async only macros, which have no meaning in sync code, and why
+const ASYNC_ONLY_MACROS: &[(&str, &str)] = &[
+    ("tokio::select", SELECT),
+    ("futures::select", SELECT),
+    ("futures::select_biased", SELECT),
+];
+
+/// Report async only APIs in code converted to sync, pointing at the
+/// original span.
+pub fn async_only_apis(file: &File) -> Vec<Error> {
+    let mut lint = AsyncOnlyApis { errors: vec![] };
+    lint.visit_file(file);
+    lint.errors
+}
+
+struct AsyncOnlyApis {
+    errors: Vec<Error>,
+}
+
+impl AsyncOnlyApis {
+    fn report(&mut self, path: &Path, api: &str, reason: &str) {
+        let msg = format!(
+            "`{}` has no sync equivalent, {}. Put the code using it in an \
+             `#[maybe_async::async_impl]` item, and write the blocking version in a \
+             `#[maybe_async::sync_impl]` item",
+            api, reason
+        );
+        self.errors.push(Error::new_spanned(path, msg));
+    }
+}
+
+impl<'ast> Visit<'ast> for AsyncOnlyApis {
+    fn visit_expr_path(&mut self, i: &'ast ExprPath) {
+        // single segment paths like `spawn` are too ambiguous
+        if i.path.segments.len() > 1 {
+            if let Some((api, reason)) = find_api(ASYNC_ONLY_FNS, &i.path) {
+                self.report(&i.path, api, reason);
+            }
+        }
+        visit::visit_expr_path(self, i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast Macro) {
+        // a bare `select!` may be a macro of the crate itself
+        if i.path.segments.len() > 1 {
+            if let Some((api, reason)) = find_api(ASYNC_ONLY_MACROS, &i.path) {
+                self.report(&i.path, api, reason);
+            }
+        }
+        visit::visit_macro(self, i);
+    }
+}
+
+/// Find the API `path` refers to, `path` may omit leading segments like
+/// `task::spawn` for `tokio::task::spawn`
+fn find_api<'a>(catalogue: &'a [(&str, &str)], path: &Path) -> Option<&'a (&'a str, &'a str)> {
+    let segments = path_segments(path);
+    catalogue.iter().find(|(api, _)| {
+        let api = api.split("::").collect::<Vec<_>>();
+        api.len() >= segments.len() && api[api.len() - segments.len()..] == segments[..]
+    })
+}
+
+const BLOCKING_SLEEP: &str = "use the timer of the async runtime instead";
+const BLOCKING_IO: &str = "use the async version of the runtime, or move the call into \
+                           `spawn_blocking`";
+const BLOCKING_REQWEST: &str = "use the async `reqwest::Client` instead";
+const BLOCKING_CUSTOM: &str = "move the call into `spawn_blocking`";
+
+/// Blocking APIs, which stall the executor in async code, and what to do
+/// instead. Module paths like `reqwest::blocking` cover everything in them.
+const BLOCKING_APIS: &[(&str, &str)] = &[
+    ("std::thread::sleep", BLOCKING_SLEEP),
+    ("std::fs::read", BLOCKING_IO),
+    ("std::fs::read_to_string", BLOCKING_IO),
+    ("std::fs::read_dir", BLOCKING_IO),
+    ("std::fs::write", BLOCKING_IO),
+    ("std::fs::copy", BLOCKING_IO),
+    ("std::fs::rename", BLOCKING_IO),
+    ("std::fs::metadata", BLOCKING_IO),
+    ("std::fs::create_dir", BLOCKING_IO),
+    ("std::fs::create_dir_all", BLOCKING_IO),
+    ("std::fs::remove_file", BLOCKING_IO),
+    ("std::fs::remove_dir", BLOCKING_IO),
+    ("std::fs::remove_dir_all", BLOCKING_IO),
+    ("std::fs::File::open", BLOCKING_IO),
+    ("std::fs::File::create", BLOCKING_IO),
+    ("std::net::TcpStream::connect", BLOCKING_IO),
+    ("std::net::TcpListener::bind", BLOCKING_IO),
+    ("std::net::UdpSocket::bind", BLOCKING_IO),
+    ("reqwest::blocking", BLOCKING_REQWEST),
+];
+
+/// Report blocking APIs in code converted to async, pointing at the original
+/// span. `denylist` extends the built-in list.
+///
+/// In `strict` mode the calls are returned as errors, otherwise a
+/// `#[deprecated]` shim is inserted into the enclosing function so rustc warns
+/// at the call without failing the build.
+pub fn blocking_apis(item: &mut Item, denylist: &[Path], strict: bool) -> Vec<Error> {
+    let denylist = denylist
+        .iter()
+        .map(|path| (path_segments(path), BLOCKING_CUSTOM))
+        .chain(
+            BLOCKING_APIS
+                .iter()
+                .map(|(api, reason)| (api.split("::").map(String::from).collect(), *reason)),
+        )
+        .collect::<Vec<_>>();
+    let mut lint = BlockingApis {
+        denylist,
+        strict,
+        errors: vec![],
+    };
+    item.visit_mut(&mut lint);
+    lint.errors
+}
+
+struct BlockingApis {
+    denylist: Vec<(Vec<String>, &'static str)>,
+    strict: bool,
+    errors: Vec<Error>,
+}
+
+impl BlockingApis {
+    fn check_block(&mut self, block: &mut Block) {
+        let mut calls = BlockingCalls {
+            denylist: &self.denylist,
+            calls: vec![],
+        };
+        calls.visit_block(block);
+        if self.strict {
+            let errors = calls
+                .calls
+                .into_iter()
+                .map(|(path, msg)| Error::new_spanned(path, msg));
+            self.errors.extend(errors);
+            return;
+        }
+        // every shim has its own scope so the constants don't clash, and the
+        // warning points at the name of the called function
+        let shims = calls.calls.into_iter().rev().map(|(path, note)| {
+            let span = path.segments.last().unwrap().ident.span();
+            let usage = quote_spanned!(span=> BLOCKING_CALL);
+            parse_quote!({
+                #[deprecated(note = #note)]
+                const BLOCKING_CALL: () = ();
+                #[allow(clippy::let_unit_value)]
+                let _ = #usage;
+            })
+        });
+        for shim in shims {
+            block.stmts.insert(0, shim);
+        }
+    }
+}
+
+impl VisitMut for BlockingApis {
+    fn visit_item_fn_mut(&mut self, i: &mut ItemFn) {
+        visit_mut::visit_item_fn_mut(self, i);
+        if i.sig.asyncness.is_some() {
+            self.check_block(&mut i.block);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, i: &mut ImplItemFn) {
+        visit_mut::visit_impl_item_fn_mut(self, i);
+        if i.sig.asyncness.is_some() {
+            self.check_block(&mut i.block);
+        }
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, i: &mut TraitItemFn) {
+        visit_mut::visit_trait_item_fn_mut(self, i);
+        if let (Some(_), Some(block)) = (i.sig.asyncness, &mut i.default) {
+            self.check_block(block);
+        }
+    }
+}
+
+/// Find calls to blocking APIs in the body of an async function
+struct BlockingCalls<'a> {
+    denylist: &'a [(Vec<String>, &'static str)],
+    calls: Vec<(Path, String)>,
+}
+
+impl<'a, 'ast> Visit<'ast> for BlockingCalls<'a> {
+    // nested functions are checked on their own if they are async
+    fn visit_item(&mut self, _i: &'ast syn::Item) {}
+
+    fn visit_expr_path(&mut self, i: &'ast ExprPath) {
+        let segments = path_segments(&i.path);
+        let found = self.denylist.iter().find(|(api, _)| {
+            // allow leading segments to be omitted, as in `thread::sleep`, as
+            // long as at least two are left
+            (0..=api.len().saturating_sub(2)).any(|skip| segments.starts_with(&api[skip..]))
+        });
+        if let Some((api, reason)) = found {
+            // name items of denied modules, like `reqwest::blocking::get`, as written
+            let api = if segments.len() > api.len() {
+                &segments
+            } else {
+                api
+            };
+            let msg = format!(
+                "`{}` blocks the executor in the async version, {}. Or write the async \
+                 version in an `#[maybe_async::async_impl]` item",
+                api.join("::"),
+                reason
+            );
+            self.calls.push((i.path.clone(), msg));
+        }
+        visit::visit_expr_path(self, i);
+    }
+
+    fn visit_expr_await(&mut self, i: &'ast ExprAwait) {
+        // an awaited call returns a future, like `tokio::fs::read(path).await`
+        // after `use tokio::fs`, so it is not blocking
+        if let Expr::Call(call) = &*i.base {
+            if let Expr::Path(_) = &*call.func {
+                if !runs_blocking_code(call) {
+                    for arg in &call.args {
+                        self.visit_expr(arg);
+                    }
+                }
+                return;
+            }
+        }
+        visit::visit_expr_await(self, i);
+    }
+
+    fn visit_expr_call(&mut self, i: &'ast ExprCall) {
+        if !runs_blocking_code(i) {
+            visit::visit_expr_call(self, i);
+        }
+    }
+}
+
+/// Whether the call is a `spawn_blocking` or `block_in_place`, where
+/// blocking code is fine
+fn runs_blocking_code(call: &ExprCall) -> bool {
+    match &*call.func {
+        Expr::Path(func) => {
+            func.path.segments.last().is_some_and(|last| {
+                last.ident == "spawn_blocking" || last.ident == "block_in_place"
+            })
+        }
+        _ => false,
+    }
+}
+
+fn path_segments(path: &Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect()
+}
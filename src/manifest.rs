@@ -0,0 +1,106 @@
+use std::{env, fs, path::PathBuf};
+
+use toml::{Table, Value};
+
+/// Path of the manifest of the crate being compiled, `None` outside of cargo
+pub fn path() -> Option<PathBuf> {
+    let dir = env::var_os("CARGO_MANIFEST_DIR")?;
+    Some(PathBuf::from(dir).join("Cargo.toml")).filter(|path| path.is_file())
+}
+
+/// Read the manifest of the crate being compiled, empty if there is no
+/// manifest, like outside of cargo.
+fn manifest() -> Result<Table, String> {
+    let Some(manifest) = path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Ok(Table::new());
+    };
+    manifest
+        .parse::<Table>()
+        .map_err(|err| format!("Failed to parse Cargo.toml: {}", err))
+}
+
+/// Read `[package.metadata.maybe-async]` from the manifest of the crate being
+/// compiled
+fn metadata() -> Result<Table, String> {
+    let manifest = manifest()?;
+    let metadata = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("maybe-async"));
+    match metadata {
+        Some(Value::Table(metadata)) => Ok(metadata.clone()),
+        Some(_) => Err("`package.metadata.maybe-async` in Cargo.toml must be a table".into()),
+        None => Ok(Table::new()),
+    }
+}
+
+/// Dual mode types registered for the whole crate, whose references are
+/// renamed in every converted item
+///
+/// ```toml
+/// [package.metadata.maybe-async]
+/// types = ["Inner", "Transport"]
+/// ```
+pub fn registered_types() -> Result<Vec<String>, String> {
+    let error = || "`types` in `package.metadata.maybe-async` must be an array of type names";
+    match metadata()?.remove("types") {
+        Some(Value::Array(types)) => types
+            .into_iter()
+            .map(|ty| match ty {
+                Value::String(ty) => Ok(ty),
+                _ => Err(error().to_string()),
+            })
+            .collect(),
+        Some(_) => Err(error().to_string()),
+        None => Ok(vec![]),
+    }
+}
+
+/// Name of the dependency on `package` in the manifest, as used in code, when
+/// it is renamed like `mba = { package = "maybe-async" }`. Looks into the
+/// normal, dev and build dependencies, also the platform specific ones.
+pub fn dependency_name(package: &str) -> Option<String> {
+    let manifest = manifest().ok()?;
+    dependency_key(&manifest, package).map(|key| key.replace('-', "_"))
+}
+
+/// The feature of the manifest enabling `feature` of maybe-async, like
+/// `blocking` for `blocking = ["maybe-async/is_sync"]`
+pub fn enabling_feature(feature: &str) -> Option<String> {
+    let manifest = manifest().ok()?;
+    let key = dependency_key(&manifest, "maybe-async").unwrap_or_else(|| "maybe-async".into());
+    let enabled = [
+        format!("{}/{}", key, feature),
+        format!("{}?/{}", key, feature),
+    ];
+    manifest
+        .get("features")
+        .and_then(Value::as_table)?
+        .iter()
+        .find(|(_, enables)| {
+            enables.as_array().is_some_and(|enables| {
+                enables
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .any(|enable| enabled.iter().any(|enabled| enabled == enable))
+            })
+        })
+        .map(|(name, _)| name.clone())
+}
+
+/// The key of the renamed dependency on `package`, as written in `manifest`
+fn dependency_key(manifest: &Table, package: &str) -> Option<String> {
+    const KINDS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+    let targets = manifest
+        .get("target")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flat_map(|targets| targets.values().filter_map(Value::as_table));
+    std::iter::once(manifest)
+        .chain(targets)
+        .flat_map(|table| KINDS.iter().filter_map(move |kind| table.get(*kind)))
+        .filter_map(Value::as_table)
+        .flat_map(|dependencies| dependencies.iter())
+        .find(|(_, dependency)| dependency.get("package").and_then(Value::as_str) == Some(package))
+        .map(|(key, _)| key.clone())
+}
@@ -1,8 +1,188 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
 use syn::{
+    braced, parenthesized,
     parse::{Parse, ParseStream, Result},
-    Attribute, ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, Token,
+    punctuated::Punctuated,
+    token,
+    visit_mut::VisitMut,
+    Attribute, Error, Expr, Ident, ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, ItemType,
+    Lit, MacroDelimiter, Meta, MetaList, MetaNameValue, Path, Token,
 };
 
+/// Arguments of the conversion macros, like `#[maybe_async::both(?Send)]`
+pub struct Args {
+    /// Whether the async version requires `Send` futures
+    pub send: bool,
+    /// Extra macros whose arguments are expressions and should be converted,
+    /// passed by `macros(tracing::info, ...)`
+    pub macros: Vec<Path>,
+    /// Blocking APIs to look for in the async version, in addition to the
+    /// built-in ones, `None` if the lint is off. Enabled by `blocking` or
+    /// `blocking(std::io::stdin, ...)`
+    pub blocking: Option<Vec<Path>>,
+    /// Whether blocking APIs in the async version are errors instead of
+    /// warnings, implies `blocking`
+    pub strict: bool,
+    /// Dual mode types referenced by the item, whose references are renamed
+    /// like the item itself, passed by `types(Inner, Transport)`
+    pub types: Vec<Ident>,
+    /// Whether the item is the same in both versions and emitted once under
+    /// its original name, by `shared`
+    pub shared: bool,
+    /// Attribute macros removed from the sync version, in addition to the
+    /// built-in ones like `tokio::main`, passed by
+    /// `async_only_attrs(tracing::instrument, ...)`
+    pub async_only_attrs: Vec<Path>,
+    /// Path of this crate in the generated code, when it is renamed or
+    /// re-exported, passed by `crate = my_facade::maybe_async`
+    pub crate_path: Option<Path>,
+    /// Path of the async-trait crate in the generated code, passed by
+    /// `async_trait = my_facade::async_trait`
+    pub async_trait: Option<Path>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            send: true,
+            macros: vec![],
+            blocking: None,
+            strict: false,
+            types: vec![],
+            shared: false,
+            async_only_attrs: vec![],
+            crate_path: None,
+            async_trait: None,
+        }
+    }
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = Args::default();
+        while !input.is_empty() {
+            if input.peek(Token![?]) {
+                input.parse::<Token![?]>()?;
+                let ident: Ident = input.parse()?;
+                if ident != "Send" {
+                    return Err(Error::new(ident.span(), "Only accepts `?Send`"));
+                }
+                args.send = false;
+            } else if input.peek(Token![crate]) {
+                // `crate` is a keyword, which is not accepted as an ident
+                input.parse::<Token![crate]>()?;
+                input.parse::<Token![=]>()?;
+                args.crate_path = Some(Path::parse_mod_style(input)?);
+            } else {
+                let ident: Ident = input.parse()?;
+                if ident == "Send" {
+                    args.send = true;
+                } else if ident == "macros" {
+                    let content;
+                    parenthesized!(content in input);
+                    let macros = Punctuated::<Path, Token![,]>::parse_terminated_with(
+                        &content,
+                        Path::parse_mod_style,
+                    )?;
+                    args.macros.extend(macros);
+                } else if ident == "blocking" {
+                    let blocking = args.blocking.get_or_insert_with(Vec::new);
+                    if input.peek(token::Paren) {
+                        let content;
+                        parenthesized!(content in input);
+                        let apis = Punctuated::<Path, Token![,]>::parse_terminated_with(
+                            &content,
+                            Path::parse_mod_style,
+                        )?;
+                        blocking.extend(apis);
+                    }
+                } else if ident == "strict" {
+                    args.blocking.get_or_insert_with(Vec::new);
+                    args.strict = true;
+                } else if ident == "shared" {
+                    args.shared = true;
+                } else if ident == "types" {
+                    let content;
+                    parenthesized!(content in input);
+                    let types = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                    args.types.extend(types);
+                } else if ident == "async_only_attrs" {
+                    let content;
+                    parenthesized!(content in input);
+                    let attrs = Punctuated::<Path, Token![,]>::parse_terminated_with(
+                        &content,
+                        Path::parse_mod_style,
+                    )?;
+                    args.async_only_attrs.extend(attrs);
+                } else if ident == "async_trait" {
+                    input.parse::<Token![=]>()?;
+                    args.async_trait = Some(Path::parse_mod_style(input)?);
+                } else {
+                    return Err(Error::new(
+                        ident.span(),
+                        "Only accepts `Send`, `?Send`, `macros(...)`, `blocking(...)`, `strict`, \
+                         `types(...)`, `shared`, `async_only_attrs(...)`, `crate = path` or \
+                         `async_trait = path`",
+                    ));
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Arguments of attribute macros, like `feature = "is_sync"` or
+/// `async(cond, tokio::test)` or `async = tokio::test`
+pub type AttributeArgs = Punctuated<NestedMeta, Token![,]>;
+
+/// A meta item or a literal in the arguments of an attribute macro
+#[allow(clippy::large_enum_variant)]
+pub enum NestedMeta {
+    Meta(Meta),
+    Lit(Lit),
+}
+
+impl Parse for NestedMeta {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(Lit) {
+            input.parse().map(NestedMeta::Lit)
+        } else if input.peek(Token![async]) {
+            // `async` is a keyword, which is not accepted as a meta path
+            let async_token: Token![async] = input.parse()?;
+            let path = Path::from(Ident::new("async", async_token.span));
+            if input.peek(Token![=]) {
+                return Ok(NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    eq_token: input.parse()?,
+                    value: input.parse()?,
+                })));
+            }
+            let content;
+            let paren = parenthesized!(content in input);
+            Ok(NestedMeta::Meta(Meta::List(MetaList {
+                path,
+                delimiter: MacroDelimiter::Paren(paren),
+                tokens: content.parse()?,
+            })))
+        } else {
+            input.parse().map(NestedMeta::Meta)
+        }
+    }
+}
+
+impl ToTokens for NestedMeta {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            NestedMeta::Meta(meta) => meta.to_tokens(tokens),
+            NestedMeta::Lit(lit) => lit.to_tokens(tokens),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum Item {
     Trait(ItemTrait),
@@ -10,51 +190,109 @@ pub enum Item {
     Enum(ItemEnum),
     Impl(ItemImpl),
     Fn(ItemFn),
+    Type(ItemType),
+}
+
+impl Item {
+    pub fn attrs_mut(&mut self) -> &mut Vec<Attribute> {
+        match self {
+            Item::Trait(item) => &mut item.attrs,
+            Item::Struct(item) => &mut item.attrs,
+            Item::Enum(item) => &mut item.attrs,
+            Item::Impl(item) => &mut item.attrs,
+            Item::Fn(item) => &mut item.attrs,
+            Item::Type(item) => &mut item.attrs,
+        }
+    }
+
+    /// Run `visitor` on the wrapped syn item
+    pub fn visit_mut<V: VisitMut>(&mut self, visitor: &mut V) {
+        match self {
+            Item::Trait(item) => visitor.visit_item_trait_mut(item),
+            Item::Struct(item) => visitor.visit_item_struct_mut(item),
+            Item::Enum(item) => visitor.visit_item_enum_mut(item),
+            Item::Impl(item) => visitor.visit_item_impl_mut(item),
+            Item::Fn(item) => visitor.visit_item_fn_mut(item),
+            Item::Type(item) => visitor.visit_item_type_mut(item),
+        }
+    }
+}
+
+impl ToTokens for Item {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Item::Trait(item) => item.to_tokens(tokens),
+            Item::Struct(item) => item.to_tokens(tokens),
+            Item::Enum(item) => item.to_tokens(tokens),
+            Item::Impl(item) => item.to_tokens(tokens),
+            Item::Fn(item) => item.to_tokens(tokens),
+            Item::Type(item) => item.to_tokens(tokens),
+        }
+    }
 }
+
 impl Parse for Item {
     fn parse(input: ParseStream) -> Result<Self> {
-        let attrs = input.call(Attribute::parse_outer)?;
-        let mut lookahead = input.lookahead1();
-        if lookahead.peek(Token![unsafe]) {
-            let ahead = input.fork();
-            ahead.parse::<Token![unsafe]>()?;
-            lookahead = ahead.lookahead1();
-        }
-        if lookahead.peek(Token![impl]) {
-            let mut item: ItemImpl = input.parse()?;
-            item.attrs = attrs;
-            Ok(Item::Impl(item))
-        } else if lookahead.peek(Token![pub])
-            || lookahead.peek(Token![trait])
-            || lookahead.peek(Token![fn])
-            || lookahead.peek(Token![async])
-            || lookahead.peek(Token![enum])
-            || lookahead.peek(Token![struct])
-        {
-            if lookahead.peek(Token![pub]) {
-                let ahead = input.fork();
-                ahead.parse::<Token![pub]>()?;
-                lookahead = ahead.lookahead1();
+        match input.parse()? {
+            syn::Item::Trait(item) => Ok(Item::Trait(item)),
+            syn::Item::Struct(item) => Ok(Item::Struct(item)),
+            syn::Item::Enum(item) => Ok(Item::Enum(item)),
+            syn::Item::Impl(item) => Ok(Item::Impl(item)),
+            syn::Item::Fn(item) => Ok(Item::Fn(item)),
+            syn::Item::Type(item) => Ok(Item::Type(item)),
+            item => Err(Error::new_spanned(
+                item,
+                "expected trait, impl, fn, struct, enum or type alias",
+            )),
+        }
+    }
+}
+
+/// Arms of `select_mode!`, like `sync => a(), async => b().await`
+pub struct SelectModeArms {
+    pub sync: Option<ModeArm>,
+    pub async_: Option<ModeArm>,
+}
+
+/// Code of one version in `select_mode!`
+pub enum ModeArm {
+    /// Statements or items in braces, without the braces
+    Braced(TokenStream),
+    Expr(Expr),
+}
+
+impl Parse for SelectModeArms {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut arms = SelectModeArms {
+            sync: None,
+            async_: None,
+        };
+        while !input.is_empty() {
+            let (arm, name) = if input.peek(Token![async]) {
+                let token = input.parse::<Token![async]>()?;
+                (&mut arms.async_, Ident::new("async", token.span))
+            } else {
+                let ident: Ident = input.parse()?;
+                if ident != "sync" {
+                    return Err(Error::new(ident.span(), "expected `sync` or `async`"));
+                }
+                (&mut arms.sync, ident)
+            };
+            if arm.is_some() {
+                return Err(Error::new(name.span(), format!("duplicate `{}` arm", name)));
             }
-            if lookahead.peek(Token![trait]) {
-                let mut item: ItemTrait = input.parse()?;
-                item.attrs = attrs;
-                Ok(Item::Trait(item))
-            } else if lookahead.peek(Token![enum]) {
-                let mut item: ItemEnum = input.parse()?;
-                item.attrs = attrs;
-                Ok(Item::Enum(item))
-            } else if lookahead.peek(Token![struct]) {
-                let mut item: ItemStruct = input.parse()?;
-                item.attrs = attrs;
-                Ok(Item::Struct(item))
+            input.parse::<Token![=>]>()?;
+            *arm = Some(if input.peek(token::Brace) {
+                let content;
+                braced!(content in input);
+                ModeArm::Braced(content.parse()?)
             } else {
-                let mut item: ItemFn = input.parse()?;
-                item.attrs = attrs;
-                Ok(Item::Fn(item))
+                ModeArm::Expr(input.parse()?)
+            });
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
             }
-        } else {
-            Err(lookahead.error())
         }
+        Ok(arms)
     }
 }
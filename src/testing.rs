@@ -0,0 +1,682 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, visit_mut::VisitMut, Block, Error, Expr,
+    FnArg, Ident, ItemFn, Lit, Meta, MetaList, Path, Result, Token,
+};
+
+use crate::{
+    convert_async, convert_sync, dual_mode_types,
+    parse::{Args, AttributeArgs, Item, NestedMeta},
+    visit::RenameAwaitedCalls,
+};
+
+/// How the async version of a test is run
+pub enum Runtime {
+    /// A test attribute macro, like `tokio::test` or
+    /// `tokio::test(flavor = "multi_thread")`
+    Macro(TokenStream),
+    /// A `block_on` function driving the test under `#[test]`, for runtimes
+    /// without a test macro
+    BlockOn(TokenStream),
+}
+
+impl Runtime {
+    /// Parse a test macro, or a shorthand like `tokio`,
+    /// `tokio(flavor = "multi_thread")`, `async_std`, `smol` or
+    /// `futures_executor`
+    pub fn parse(meta: &NestedMeta) -> Result<Self> {
+        let meta = match meta {
+            NestedMeta::Meta(meta) => meta.clone(),
+            NestedMeta::Lit(Lit::Str(s)) => s.parse()?,
+            NestedMeta::Lit(lit) => {
+                return Err(Error::new(lit.span(), "expected meta or string literal"))
+            }
+        };
+        match &meta {
+            Meta::Path(path) if path.is_ident("tokio") => Ok(Runtime::Macro(quote!(tokio::test))),
+            Meta::Path(path) if path.is_ident("async_std") => {
+                Ok(Runtime::Macro(quote!(async_std::test)))
+            }
+            Meta::Path(path) if path.is_ident("smol") => {
+                Ok(Runtime::BlockOn(quote!(smol::block_on)))
+            }
+            Meta::Path(path) if path.is_ident("futures_executor") => {
+                Ok(Runtime::BlockOn(quote!(futures_executor::block_on)))
+            }
+            Meta::List(list) if list.path.is_ident("tokio") => {
+                let tokens = &list.tokens;
+                Ok(Runtime::Macro(quote!(tokio::test(#tokens))))
+            }
+            Meta::NameValue(_) => Err(Error::new_spanned(
+                meta,
+                "expected a test macro like `tokio::test`, or a runtime like `tokio`",
+            )),
+            _ => Ok(Runtime::Macro(quote!(#meta))),
+        }
+    }
+
+    /// Parse the runtimes of `matrix(tokio, async_std, smol)`
+    pub fn parse_matrix(list: &MetaList) -> Result<Vec<Self>> {
+        let runtimes = list
+            .parse_args_with(AttributeArgs::parse_terminated)?
+            .iter()
+            .map(Runtime::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if runtimes.is_empty() {
+            return Err(Error::new_spanned(
+                list,
+                "`matrix` needs at least one runtime",
+            ));
+        }
+        for (i, runtime) in runtimes.iter().enumerate() {
+            if runtimes[..i]
+                .iter()
+                .any(|other| other.name() == runtime.name())
+            {
+                return Err(Error::new_spanned(
+                    list,
+                    format!("duplicate runtime `{}` in `matrix`", runtime.name()),
+                ));
+            }
+        }
+        Ok(runtimes)
+    }
+
+    /// Name of the runtime in test names, the crate of its test macro or
+    /// `block_on`, like `tokio`
+    fn name(&self) -> String {
+        let (Runtime::Macro(tokens) | Runtime::BlockOn(tokens)) = self;
+        tokens
+            .clone()
+            .into_iter()
+            .next()
+            .map(|first| first.to_string())
+            .unwrap_or_default()
+    }
+
+    /// The timeout function of the runtime, wrapping the async test body
+    fn timeout_fn(&self) -> Option<TokenStream> {
+        match (self, self.name().as_str()) {
+            (Runtime::Macro(_), "tokio") => Some(quote!(::tokio::time::timeout)),
+            (Runtime::Macro(_), "async_std") => Some(quote!(::async_std::future::timeout)),
+            _ => None,
+        }
+    }
+}
+
+/// Timeout of a test, passed by `timeout = "5s"`
+pub struct Timeout {
+    millis: u64,
+    text: String,
+    span: Span,
+}
+
+impl Timeout {
+    /// Parse a duration in `ms`, `s` or `m`, like `"500ms"` or `"5s"`
+    pub fn parse(value: &Expr) -> Result<Self> {
+        let error = || {
+            Error::new_spanned(
+                value,
+                "expected a duration like `timeout = \"500ms\"`, `\"5s\"` or `\"1m\"`",
+            )
+        };
+        let Expr::Lit(lit) = value else {
+            return Err(error());
+        };
+        let Lit::Str(s) = &lit.lit else {
+            return Err(error());
+        };
+        let text = s.value();
+        let split = text.find(|c: char| !c.is_ascii_digit()).ok_or_else(error)?;
+        let amount: u64 = text[..split].parse().map_err(|_| error())?;
+        let millis = match &text[split..] {
+            "ms" => amount,
+            "s" => amount * 1000,
+            "m" => amount * 60 * 1000,
+            _ => return Err(error()),
+        };
+        Ok(Self {
+            millis,
+            text,
+            span: s.span(),
+        })
+    }
+
+    fn message(&self) -> String {
+        format!("test timed out after {}", self.text)
+    }
+}
+
+/// The sync version of `test`, failed when it runs longer than `timeout`
+pub fn sync_test(mut test: ItemFn, timeout: Option<&Timeout>) -> ItemFn {
+    if let Some(timeout) = timeout {
+        test.block = Box::new(watchdog(&test, timeout));
+    }
+    test
+}
+
+/// The async version of `test` run by `runtime`, with the attribute to put on
+/// it
+pub fn async_test(
+    mut test: ItemFn,
+    runtime: &Runtime,
+    timeout: Option<&Timeout>,
+) -> Result<(TokenStream, ItemFn)> {
+    match runtime {
+        Runtime::Macro(attr) => {
+            if let Some(timeout) = timeout {
+                let timeout_fn = runtime.timeout_fn().ok_or_else(|| {
+                    Error::new(
+                        timeout.span,
+                        "`timeout` needs a known runtime: `tokio`, `async_std`, `smol` or \
+                         `futures_executor`",
+                    )
+                })?;
+                let block = &test.block;
+                let millis = timeout.millis;
+                let message = timeout.message();
+                test.block = parse_quote!({
+                    match #timeout_fn(
+                        ::std::time::Duration::from_millis(#millis),
+                        async move #block,
+                    )
+                    .await
+                    {
+                        ::std::result::Result::Ok(output) => output,
+                        ::std::result::Result::Err(_) => ::std::panic!(#message),
+                    }
+                });
+            }
+            Ok((quote!(#[#attr]), test))
+        }
+        Runtime::BlockOn(block_on) => {
+            let block = &test.block;
+            test.block = parse_quote!({ #block_on(async move #block) });
+            test.sig.asyncness = None;
+            Ok((quote!(#[test]), sync_test(test, timeout)))
+        }
+    }
+}
+
+/// The async versions of `test` run by each runtime of a matrix, active under
+/// `cfg` if any in the same build and named like `test_x__tokio`
+pub fn matrix_tests(
+    test: &ItemFn,
+    runtimes: &[Runtime],
+    timeout: Option<&Timeout>,
+    cfg: Option<&TokenStream>,
+    args: &Args,
+) -> Result<TokenStream> {
+    let cfg = cfg.map(|cfg| quote!(#[cfg(#cfg)]));
+    let converted_name = format!("{}_async", test.sig.ident);
+    let mut tests = TokenStream::new();
+    for runtime in runtimes {
+        let (attr, async_test) = async_test(test.clone(), runtime, timeout)?;
+        let name = Ident::new(
+            &format!("{}__{}", test.sig.ident, runtime.name()),
+            test.sig.ident.span(),
+        );
+        let async_test = rename_fn(
+            convert_async(Item::Fn(async_test), args),
+            &converted_name,
+            &name,
+        );
+        tests.extend(quote!(
+            #cfg
+            #attr
+            #[allow(non_snake_case)]
+            #async_test
+        ));
+    }
+    Ok(tests)
+}
+
+/// Rename the function `from` converted by `convert_async`, whose name is the
+/// only identifier outside of groups matching it
+fn rename_fn(tokens: TokenStream, from: &str, to: &Ident) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Ident(ident) if ident == from => TokenTree::Ident(to.clone()),
+            token => token,
+        })
+        .collect()
+}
+
+/// Split a test with `#[case(...)]` attributes into a test per case, like
+/// `rstest`. The arguments of a case are bound to the parameters of the test,
+/// and the test is named like `test_x_case_1`, or `test_x_empty` with
+/// `#[case::empty(...)]`
+pub fn cases(mut test: ItemFn) -> Result<Vec<ItemFn>> {
+    let mut cases = vec![];
+    test.attrs.retain(|attr| {
+        let is_case = attr
+            .path()
+            .segments
+            .first()
+            .is_some_and(|first| first.ident == "case");
+        if is_case {
+            cases.push(attr.clone());
+        }
+        !is_case
+    });
+    if cases.is_empty() {
+        return Ok(vec![test]);
+    }
+
+    let params = test
+        .sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            FnArg::Typed(param) => Ok(param),
+            FnArg::Receiver(receiver) => Err(Error::new_spanned(
+                receiver,
+                "a test with cases can not take `self`",
+            )),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let ident = &test.sig.ident;
+    let block = &test.block;
+    cases
+        .iter()
+        .enumerate()
+        .map(|(i, attr)| {
+            let args = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+            if args.len() != params.len() {
+                return Err(Error::new_spanned(
+                    attr,
+                    format!(
+                        "expected {} arguments, like the parameters of `{}`, found {}",
+                        params.len(),
+                        ident,
+                        args.len()
+                    ),
+                ));
+            }
+            let name = match attr.path().segments.iter().nth(1) {
+                Some(name) => format!("{}_{}", ident, name.ident),
+                None => format!("{}_case_{}", ident, i + 1),
+            };
+            let bindings = params.iter().zip(&args).map(|(param, arg)| {
+                let pat = &param.pat;
+                let ty = &param.ty;
+                quote!(let #pat: #ty = #arg;)
+            });
+            let mut case = test.clone();
+            case.sig.ident = Ident::new(&name, ident.span());
+            case.sig.inputs = Punctuated::new();
+            case.block = parse_quote!({
+                #(#bindings)*
+                #block
+            });
+            Ok(case)
+        })
+        .collect()
+}
+
+/// Compile errors for the conditions of `test` met at the same time, which
+/// would define it twice: the sync condition with an async one, or two async
+/// conditions
+pub fn overlapping_conditions(
+    test: &Ident,
+    sync_cond: &TokenStream,
+    async_conds: &[TokenStream],
+) -> TokenStream {
+    let mut checks = TokenStream::new();
+    let mut check =
+        |first: &str, first_cond: &TokenStream, second: &str, second_cond: &TokenStream| {
+            let message = format!(
+                "the {} condition `{}` and the {} condition `{}` of `{}` are both met, add \
+             `not(...)` to one of them",
+                first, first_cond, second, second_cond, test
+            );
+            checks.extend(quote!(
+                #[cfg(all(#first_cond, #second_cond))]
+                ::core::compile_error!(#message);
+            ));
+        };
+    for (i, async_cond) in async_conds.iter().enumerate() {
+        check("sync", sync_cond, "async", async_cond);
+        for other in &async_conds[i + 1..] {
+            check("async", async_cond, "async", other);
+        }
+    }
+    checks
+}
+
+/// Run the body of `test` on the test thread, which keeps its thread locals
+/// and the setup of the harness, and fail it once done if it took longer than
+/// `timeout`. A timer thread reports a test still running at the deadline.
+fn watchdog(test: &ItemFn, timeout: &Timeout) -> Block {
+    let block = &test.block;
+    let output = &test.sig.output;
+    let millis = timeout.millis;
+    let message = timeout.message();
+    parse_quote!({
+        let deadline = ::std::time::Duration::from_millis(#millis);
+        let (done, finished) = ::std::sync::mpsc::channel::<()>();
+        ::std::thread::spawn(move || {
+            if let ::std::result::Result::Err(::std::sync::mpsc::RecvTimeoutError::Timeout) =
+                finished.recv_timeout(deadline)
+            {
+                ::std::eprintln!("{}", #message);
+            }
+        });
+        let started = ::std::time::Instant::now();
+        let output = (|| #output #block)();
+        ::std::mem::drop(done);
+        if started.elapsed() > deadline {
+            ::std::panic!(#message);
+        }
+        output
+    })
+}
+
+/// Arguments of a dual mode test, `#[maybe_async::test(both, ...)]`
+struct BothArgs {
+    /// Test harness macro of the sync version, `test` by default, passed by
+    /// `sync = test_log::test`
+    sync_harness: Option<TokenStream>,
+    /// Runtime of the async version, passed by `async = tokio::test` or
+    /// `async(tokio)`
+    runtime: Option<Runtime>,
+    /// Runtimes running the async version each, passed by
+    /// `matrix(tokio, async_std)`
+    matrix: Vec<Runtime>,
+    /// Dual mode types used by the test, passed by `types(Client)`
+    types: Vec<Ident>,
+    /// Dual mode functions awaited by the test, passed by `fns(fetch)`
+    fns: Vec<Ident>,
+    /// Passed by `timeout = "5s"`
+    timeout: Option<Timeout>,
+    /// Path of this crate, passed by `crate = my_facade::maybe_async`
+    crate_path: Option<Path>,
+}
+
+impl BothArgs {
+    fn parse(both: Span, args: impl IntoIterator<Item = NestedMeta>) -> Result<Self> {
+        let mut sync_harness = None;
+        let mut runtime = None;
+        let mut matrix = vec![];
+        let mut types = vec![];
+        let mut fns = vec![];
+        let mut timeout = None;
+        let mut crate_path = None;
+        for arg in args {
+            match arg {
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("sync") => {
+                    let value = &meta.value;
+                    sync_harness = Some(quote!(#value));
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("sync") => {
+                    sync_harness = Some(list.tokens);
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("async") => {
+                    let value = &meta.value;
+                    runtime = Some(Runtime::parse(&syn::parse2(quote!(#value))?)?);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("async") => {
+                    runtime = Some(Runtime::parse(&list.parse_args()?)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("timeout") => {
+                    timeout = Some(Timeout::parse(&meta.value)?);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("matrix") => {
+                    matrix.extend(Runtime::parse_matrix(&list)?);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("types") => {
+                    let idents =
+                        list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+                    types.extend(idents);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("fns") => {
+                    let idents =
+                        list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+                    fns.extend(idents);
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("crate") => {
+                    let value = &meta.value;
+                    crate_path = Some(syn::parse2(quote!(#value))?);
+                }
+                arg => {
+                    return Err(Error::new(
+                        arg.span(),
+                        "Only accepts `sync = harness_macro`, `async = test_macro`, \
+                         `async(runtime)`, `matrix(...)`, `timeout = \"5s\"`, `types(...)`, \
+                         `fns(...)` or `crate = path` after `both`",
+                    ))
+                }
+            }
+        }
+        if runtime.is_none() && matrix.is_empty() {
+            return Err(Error::new(
+                both,
+                "missing the test macro of the async version, like `async = tokio::test`",
+            ));
+        }
+        Ok(Self {
+            sync_harness,
+            runtime,
+            matrix,
+            types,
+            fns,
+            timeout,
+            crate_path,
+        })
+    }
+}
+
+/// Generate `test_x_sync` and `test_x_async` from a test, calling the items of
+/// each version
+pub fn both(args: AttributeArgs, input: Item) -> Result<TokenStream> {
+    let both = args.first().map_or_else(Span::call_site, Spanned::span);
+    let args = BothArgs::parse(both, args.into_iter().skip(1))?;
+    let Item::Fn(input) = input else {
+        return Err(Error::new_spanned(input, "`both` tests must be functions"));
+    };
+    let convert_args = Args {
+        types: args.types,
+        crate_path: args.crate_path,
+        ..Args::default()
+    };
+    let types = dual_mode_types(&convert_args)?;
+    let fns = args.fns.iter().map(ToString::to_string).collect::<Vec<_>>();
+    let timeout = args.timeout.as_ref();
+
+    let sync_harness = args.sync_harness.unwrap_or_else(|| quote!(test));
+    let mut tests = TokenStream::new();
+    // the versions enabled in maybe_async, like in `both` items, the crate
+    // using it has no `is_sync` or `is_async` feature of its own
+    for input in cases(input)? {
+        if cfg!(feature = "is_sync") {
+            let sync_test = rename_awaited_calls(&input, &types, &fns, "_sync", "Sync");
+            let sync_test = Item::Fn(self::sync_test(sync_test, timeout));
+            let sync_test = convert_sync(sync_test, &convert_args);
+            tests.extend(quote!(
+                #[#sync_harness]
+                #sync_test
+            ));
+        }
+
+        if cfg!(feature = "is_async") {
+            let async_test = rename_awaited_calls(&input, &types, &fns, "_async", "Async");
+            if let Some(runtime) = &args.runtime {
+                let (attr, async_test) = self::async_test(async_test.clone(), runtime, timeout)?;
+                let async_test = convert_async(Item::Fn(async_test), &convert_args);
+                tests.extend(quote!(
+                    #attr
+                    #async_test
+                ));
+            }
+            tests.extend(matrix_tests(
+                &async_test,
+                &args.matrix,
+                timeout,
+                None,
+                &convert_args,
+            )?);
+        }
+    }
+    Ok(tests)
+}
+
+fn rename_awaited_calls(
+    test: &ItemFn,
+    types: &[String],
+    fns: &[String],
+    fn_suffix: &str,
+    type_suffix: &str,
+) -> ItemFn {
+    let mut test = test.clone();
+    RenameAwaitedCalls::new(types, fns, fn_suffix, type_suffix).visit_item_fn_mut(&mut test);
+    test
+}
+
+/// Arguments of `#[maybe_async::bench(...)]`
+struct BenchArgs {
+    /// Statements preparing the executor, like building a tokio runtime
+    setup: TokenStream,
+    /// Executor of the async version passed to criterion's `to_async`, from
+    /// `async = tokio`, `async_std`, `smol`, `futures_executor` or an
+    /// expression
+    executor: TokenStream,
+    /// Dual mode types used by the benchmark, passed by `types(Client)`
+    types: Vec<Ident>,
+    /// Dual mode functions awaited by the benchmark, passed by `fns(fetch)`
+    fns: Vec<Ident>,
+    /// Path of this crate, passed by `crate = my_facade::maybe_async`
+    crate_path: Option<Path>,
+}
+
+impl BenchArgs {
+    fn parse(args: AttributeArgs) -> Result<Self> {
+        let mut executor = None;
+        let mut types = vec![];
+        let mut fns = vec![];
+        let mut crate_path = None;
+        for arg in args {
+            match arg {
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("async") => {
+                    executor = Some(meta.value);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("async") => {
+                    executor = Some(list.parse_args()?);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("types") => {
+                    let idents =
+                        list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+                    types.extend(idents);
+                }
+                NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("fns") => {
+                    let idents =
+                        list.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+                    fns.extend(idents);
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("crate") => {
+                    let value = &meta.value;
+                    crate_path = Some(syn::parse2(quote!(#value))?);
+                }
+                arg => return Err(Error::new(
+                    arg.span(),
+                    "Only accepts `async = runtime`, `types(...)`, `fns(...)` or `crate = path`",
+                )),
+            }
+        }
+        let executor = executor.ok_or_else(|| {
+            Error::new(
+                Span::call_site(),
+                "missing the runtime of the async version, like `async = tokio`",
+            )
+        })?;
+        let runtime = match &executor {
+            Expr::Path(path) => path.path.get_ident().map(ToString::to_string),
+            _ => None,
+        };
+        let (setup, executor) = match runtime.as_deref() {
+            Some("tokio") => (
+                quote!(
+                    let runtime = ::tokio::runtime::Runtime::new()
+                        .expect("failed to build the tokio runtime");
+                ),
+                quote!(&runtime),
+            ),
+            Some("async_std") => (
+                quote!(),
+                quote!(::criterion::async_executor::AsyncStdExecutor),
+            ),
+            Some("smol") => (quote!(), quote!(::criterion::async_executor::SmolExecutor)),
+            Some("futures_executor") => (
+                quote!(),
+                quote!(::criterion::async_executor::FuturesExecutor),
+            ),
+            _ => (quote!(), quote!(#executor)),
+        };
+        Ok(Self {
+            setup,
+            executor,
+            types,
+            fns,
+            crate_path,
+        })
+    }
+}
+
+/// Generate a criterion benchmark function running `input` in each enabled
+/// version, as the `sync` and `async` functions of one group
+pub fn bench(args: AttributeArgs, input: Item) -> Result<TokenStream> {
+    let args = BenchArgs::parse(args)?;
+    let Item::Fn(input) = input else {
+        return Err(Error::new_spanned(input, "benchmarks must be functions"));
+    };
+    if !input.sig.inputs.is_empty() {
+        return Err(Error::new_spanned(
+            &input.sig.inputs,
+            "benchmarks take no parameters",
+        ));
+    }
+    let convert_args = Args {
+        types: args.types,
+        crate_path: args.crate_path,
+        ..Args::default()
+    };
+    let types = dual_mode_types(&convert_args)?;
+    let fns = args.fns.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    let ident = &input.sig.ident;
+    let vis = &input.vis;
+    let name = ident.to_string();
+    let sync_ident = Ident::new(&format!("{}_sync", ident), ident.span());
+    let async_ident = Ident::new(&format!("{}_async", ident), ident.span());
+
+    // the versions enabled in maybe_async, like in `both` tests
+    let mut versions = TokenStream::new();
+    if cfg!(feature = "is_sync") {
+        let sync_bench = rename_awaited_calls(&input, &types, &fns, "_sync", "Sync");
+        let sync_bench = convert_sync(Item::Fn(sync_bench), &convert_args);
+        versions.extend(quote!({
+            #sync_bench
+            group.bench_function("sync", |b| b.iter(#sync_ident));
+        }));
+    }
+    if cfg!(feature = "is_async") {
+        let async_bench = rename_awaited_calls(&input, &types, &fns, "_async", "Async");
+        let async_bench = convert_async(Item::Fn(async_bench), &convert_args);
+        let setup = &args.setup;
+        let executor = &args.executor;
+        versions.extend(quote!({
+            #async_bench
+            #setup
+            group.bench_function("async", |b| b.to_async(#executor).iter(#async_ident));
+        }));
+    }
+
+    Ok(quote!(
+        #vis fn #ident(c: &mut ::criterion::Criterion) {
+            let mut group = c.benchmark_group(#name);
+            #versions
+            group.finish();
+        }
+    ))
+}
@@ -1,199 +1,1445 @@
-use std::iter::FromIterator;
-
-use proc_macro2::TokenStream;
+use proc_macro2::{Group, TokenStream, TokenTree};
 use quote::quote;
 use syn::{
+    parse::{Nothing, Parse, ParseStream, Parser},
     parse_quote,
     punctuated::Punctuated,
-    visit_mut::{self, visit_item_mut, visit_path_segment_mut, VisitMut},
-    Expr, ExprBlock, File, GenericArgument, GenericParam, Item, PathArguments, PathSegment, Type,
-    TypeParamBound, WherePredicate,
+    spanned::Spanned,
+    visit_mut::{
+        self, visit_expr_path_mut, visit_impl_item_fn_mut, visit_item_fn_mut, visit_item_impl_mut,
+        visit_item_trait_mut, visit_trait_bound_mut, visit_trait_item_fn_mut, visit_type_mut,
+        VisitMut,
+    },
+    Attribute, Block, Error, Expr, ExprAwait, ExprBlock, ExprPath, Field, FieldsNamed,
+    FieldsUnnamed, File, FnArg, GenericArgument, GenericParam, Ident, ImplItem, ImplItemFn,
+    ImplItemType, Item, ItemEnum, ItemFn, ItemImpl, ItemTrait, ItemType, Local, Macro, Meta, Path,
+    PathArguments, PathSegment, Result, Signature, Stmt, Token, TraitBound, TraitItem, TraitItemFn,
+    Type, TypeGroup, TypeImplTrait, TypeParamBound, TypeParen, TypePath, TypeTraitObject, Variant,
+    WherePredicate,
+};
+
+use crate::{
+    lint,
+    parse::{self, ModeArm, SelectModeArms},
 };
 
 pub struct ReplaceGenericType<'a> {
     generic_type: &'a str,
-    arg_type: &'a PathSegment,
+    arg_type: &'a Type,
 }
 
 impl<'a> ReplaceGenericType<'a> {
-    pub fn new(generic_type: &'a str, arg_type: &'a PathSegment) -> Self {
+    pub fn new(generic_type: &'a str, arg_type: &'a Type) -> Self {
         Self {
             generic_type,
             arg_type,
         }
     }
 
-    pub fn replace_generic_type(item: &mut Item, generic_type: &'a str, arg_type: &'a PathSegment) {
+    /// Remove `generic_type` from the generics of `sig`, then replace its
+    /// uses in the signature and in `block` with `arg_type`.
+    pub fn replace_generic_type(
+        sig: &mut Signature,
+        block: Option<&mut Block>,
+        generic_type: &'a str,
+        arg_type: &'a Type,
+    ) {
+        // remove generic type from generics <T, F>
+        sig.generics.params = sig
+            .generics
+            .params
+            .iter()
+            .filter(|param| match param {
+                GenericParam::Type(type_param) => type_param.ident != generic_type,
+                _ => true,
+            })
+            .cloned()
+            .collect();
+
+        // remove generic type from where clause
+        if let Some(where_clause) = &mut sig.generics.where_clause {
+            where_clause.predicates = where_clause
+                .predicates
+                .iter()
+                .filter(|predicate| match predicate {
+                    WherePredicate::Type(predicate_type) => {
+                        !is_generic_type(&predicate_type.bounded_ty, generic_type)
+                    }
+                    _ => true,
+                })
+                .cloned()
+                .collect();
+        }
+
         let mut s = Self::new(generic_type, arg_type);
-        s.visit_item_mut(item);
+        s.visit_signature_mut(sig);
+        if let Some(block) = block {
+            s.visit_block_mut(block);
+        }
     }
 }
 
 impl<'a> VisitMut for ReplaceGenericType<'a> {
-    fn visit_item_mut(&mut self, i: &mut Item) {
-        if let Item::Fn(item_fn) = i {
-            // remove generic type from generics <T, F>
-            let args = item_fn
-                .sig
-                .generics
-                .params
-                .iter()
-                .filter_map(|param| {
-                    if let GenericParam::Type(type_param) = &param {
-                        if type_param.ident.to_string().eq(self.generic_type) {
-                            None
-                        } else {
-                            Some(param)
-                        }
-                    } else {
-                        Some(param)
-                    }
-                })
-                .collect::<Vec<_>>();
-            item_fn.sig.generics.params =
-                Punctuated::from_iter(args.into_iter().map(|p| p.clone()).collect::<Vec<_>>());
-
-            // remove generic type from where clause
-            if let Some(where_clause) = &mut item_fn.sig.generics.where_clause {
-                let new_where_clause = where_clause
-                    .predicates
-                    .iter()
-                    .filter_map(|predicate| {
-                        if let WherePredicate::Type(predicate_type) = predicate {
-                            if let Type::Path(p) = &predicate_type.bounded_ty {
-                                if p.path.segments[0].ident.to_string().eq(self.generic_type) {
-                                    None
-                                } else {
-                                    Some(predicate)
-                                }
-                            } else {
-                                Some(predicate)
-                            }
-                        } else {
-                            Some(predicate)
+    fn visit_item_mut(&mut self, _i: &mut Item) {
+        // nested items can not use the generic parameters of the outer
+        // function, and may declare their own parameter with the same name
+    }
+
+    fn visit_type_mut(&mut self, i: &mut Type) {
+        match i {
+            Type::Path(TypePath { qself: None, path }) => {
+                if let Some(rest) = strip_generic_type(path, self.generic_type) {
+                    let arg_type = self.arg_type;
+                    *i = match rest.as_slice() {
+                        // `F` and `F::Output` are both the output type
+                        [] => arg_type.clone(),
+                        [output] if output.ident == "Output" && output.arguments.is_empty() => {
+                            arg_type.clone()
                         }
-                    })
-                    .collect::<Vec<_>>();
+                        _ => parse_quote!(<#arg_type>::#(#rest)::*),
+                    };
+                    return;
+                }
+            }
+            // `<F as Future>::Output`
+            Type::Path(TypePath {
+                qself: Some(qself),
+                path,
+            }) if is_generic_type(&qself.ty, self.generic_type)
+                && qself.position > 0
+                && qself.position + 1 == path.segments.len()
+                && path.segments[qself.position - 1]
+                    .ident
+                    .to_string()
+                    .ends_with("Future")
+                && path.segments[qself.position].ident == "Output" =>
+            {
+                *i = self.arg_type.clone();
+                return;
+            }
+            _ => {}
+        }
+        visit_type_mut(self, i);
+    }
 
-                where_clause.predicates = Punctuated::from_iter(
-                    new_where_clause
-                        .into_iter()
-                        .map(|c| c.clone())
-                        .collect::<Vec<_>>(),
-                );
-            };
+    fn visit_expr_path_mut(&mut self, i: &mut ExprPath) {
+        // `F::new()` becomes `<Output>::new()`
+        if i.qself.is_none() {
+            if let Some(rest) = strip_generic_type(&i.path, self.generic_type) {
+                if !rest.is_empty() {
+                    let arg_type = self.arg_type;
+                    let attrs = &i.attrs;
+                    *i = parse_quote!(#(#attrs)* <#arg_type>::#(#rest)::*);
+                }
+            }
         }
-        visit_item_mut(self, i)
+        visit_expr_path_mut(self, i);
+    }
+}
+
+/// If `path` starts with the bare generic type parameter `generic_type`,
+/// return the remaining segments. `crate::F` or `::F` never refer to a
+/// generic parameter.
+fn strip_generic_type<'p>(path: &'p Path, generic_type: &str) -> Option<Vec<&'p PathSegment>> {
+    let first = path.segments.first()?;
+    if path.leading_colon.is_none() && first.arguments.is_empty() && first.ident == generic_type {
+        Some(path.segments.iter().skip(1).collect())
+    } else {
+        None
     }
-    fn visit_path_segment_mut(&mut self, i: &mut PathSegment) {
-        // replace generic type with target type
-        if i.ident.to_string().eq(&self.generic_type) {
-            *i = self.arg_type.clone();
+}
+
+/// Whether `ty` is exactly the bare generic type parameter `generic_type`
+fn is_generic_type(ty: &Type, generic_type: &str) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => {
+            matches!(strip_generic_type(path, generic_type), Some(rest) if rest.is_empty())
         }
-        visit_path_segment_mut(self, i);
+        _ => false,
     }
 }
 
-pub struct AsyncAwaitRemoval;
+/// Replace the projection `Self::Name` of an associated type with `ty`
+pub struct ReplaceSelfProjection<'a> {
+    name: &'a Ident,
+    ty: &'a Type,
+}
+
+impl<'a> ReplaceSelfProjection<'a> {
+    pub fn new(name: &'a Ident, ty: &'a Type) -> Self {
+        Self { name, ty }
+    }
+}
+
+impl<'a> VisitMut for ReplaceSelfProjection<'a> {
+    fn visit_item_mut(&mut self, _i: &mut Item) {
+        // `Self` refers to another type inside nested items
+    }
+
+    fn visit_type_mut(&mut self, i: &mut Type) {
+        if let Type::Path(TypePath { qself: None, path }) = i {
+            if let Some(rest) = strip_generic_type(path, "Self") {
+                if let [name] = rest.as_slice() {
+                    if name.ident == *self.name && name.arguments.is_empty() {
+                        *i = self.ty.clone();
+                        return;
+                    }
+                }
+            }
+        }
+        visit_type_mut(self, i);
+    }
+}
+
+/// Macros whose arguments are comma separated expressions
+const EXPR_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "dbg",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "eprint",
+    "eprintln",
+    "format",
+    "format_args",
+    "panic",
+    "print",
+    "println",
+    "todo",
+    "unimplemented",
+    "unreachable",
+    "vec",
+    "write",
+    "writeln",
+];
+
+pub struct AsyncAwaitRemoval<'a> {
+    /// user provided macros whose arguments are expressions
+    macros: &'a [Path],
+    errors: Vec<Error>,
+}
+
+impl<'a> AsyncAwaitRemoval<'a> {
+    pub fn new(macros: &'a [Path]) -> Self {
+        Self {
+            macros,
+            errors: vec![],
+        }
+    }
 
-impl AsyncAwaitRemoval {
     pub fn remove_async_await(&mut self, item: TokenStream) -> TokenStream {
-        let mut syntax_tree: File = syn::parse(item.into()).unwrap();
+        let mut syntax_tree: File = match syn::parse2(item) {
+            Ok(syntax_tree) => syntax_tree,
+            Err(err) => return err.to_compile_error(),
+        };
+        // async only APIs are reported where they are used, the converted
+        // item is still emitted so its name resolves for the rest of the crate
+        self.errors.extend(lint::async_only_apis(&syntax_tree));
         self.visit_file_mut(&mut syntax_tree);
-        quote!(#syntax_tree)
+        let errors = self.errors.iter().map(Error::to_compile_error);
+        quote!(#syntax_tree #(#errors)*)
+    }
+
+    fn replace_future_types(&mut self, sig: &mut Signature, block: Option<&mut Block>) {
+        if let Err(err) = replace_future_types(sig, block) {
+            self.errors.push(err);
+        }
     }
 }
 
-impl VisitMut for AsyncAwaitRemoval {
+impl<'a> VisitMut for AsyncAwaitRemoval<'a> {
+    fn visit_macro_mut(&mut self, node: &mut Macro) {
+        let name = match node.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => return,
+        };
+        let is_expr_macro = EXPR_MACROS.contains(&name.as_str())
+            || self.macros.iter().any(|path| {
+                path.segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == name)
+            });
+
+        if is_expr_macro {
+            // parse the arguments as expressions and convert them like any
+            // other expressions
+            if let Ok(mut args) =
+                node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            {
+                for arg in &mut args {
+                    self.visit_expr_mut(arg);
+                }
+                node.tokens = quote!(#args);
+                return;
+            }
+        }
+        // unknown macros or arguments that are not expressions, like
+        // `vec![x; n]` or `info!(target: "app", ...)`
+        node.tokens = remove_await_tokens(node.tokens.clone());
+    }
+
     fn visit_expr_mut(&mut self, node: &mut Expr) {
+        // `Box::pin(async {})` and `async {}.boxed()` are only needed to name
+        // the future type
+        if let Some(expr) = boxed_async_block(node) {
+            *node = expr;
+        }
+
         // Delegate to the default impl to visit nested expressions.
         visit_mut::visit_expr_mut(self, node);
 
         match node {
-            Expr::Await(expr) => *node = (*expr.base).clone(),
+            Expr::Await(expr) => {
+                *node = match &*expr.base {
+                    // `fut.into_future().await` is the same as `fut.await`
+                    Expr::MethodCall(call)
+                        if call.method == "into_future" && call.args.is_empty() =>
+                    {
+                        (*call.receiver).clone()
+                    }
+                    base => base.clone(),
+                }
+            }
 
             Expr::Async(expr) => {
                 let inner = &expr.block;
-                let sync_expr = if inner.stmts.len() == 1 {
-                    // remove useless braces when there is only one statement
-                    let stmt = &inner.stmts.get(0).unwrap();
-                    // convert statement to Expr
-                    parse_quote!(#stmt)
-                } else {
-                    Expr::Block(ExprBlock {
+                *node = match inner.stmts.as_slice() {
+                    // remove useless braces when there is only one expression
+                    [Stmt::Expr(expr, None)] => expr.clone(),
+                    _ => Expr::Block(ExprBlock {
                         attrs: expr.attrs.clone(),
                         block: inner.clone(),
                         label: None,
-                    })
+                    }),
                 };
-                *node = sync_expr;
             }
+
+            // `async |x| x.await` becomes `|x| x`
+            Expr::Closure(closure) => closure.asyncness = None,
+
             _ => {}
         }
     }
 
-    fn visit_item_mut(&mut self, i: &mut Item) {
-        // find generic parameter of Future and replace it with its Output type
-        if let Item::Fn(item_fn) = i {
-            let mut inputs: Vec<(String, PathSegment)> = vec![];
-
-            // generic params: <T:Future<Output=()>, F>
-            for param in &item_fn.sig.generics.params {
-                // generic param: T:Future<Output=()>
-                if let GenericParam::Type(type_param) = param {
-                    let generic_type_name = type_param.ident.to_string();
-
-                    // bound: Future<Output=()>
-                    for bound in &type_param.bounds {
-                        inputs.extend(search_trait_bound(&generic_type_name, bound));
+    fn visit_trait_bound_mut(&mut self, i: &mut TraitBound) {
+        // `AsyncFn(u8) -> u8` becomes `Fn(u8) -> u8`
+        if let Some(segment) = i.path.segments.last_mut() {
+            if let PathArguments::Parenthesized(_) = segment.arguments {
+                let name = segment.ident.to_string();
+                if let Some(sync_name) = name.strip_prefix("Async") {
+                    if matches!(sync_name, "Fn" | "FnMut" | "FnOnce") {
+                        segment.ident = Ident::new(sync_name, segment.ident.span());
+                    }
+                }
+            }
+        }
+        visit_trait_bound_mut(self, i);
+    }
+
+    fn visit_item_fn_mut(&mut self, i: &mut ItemFn) {
+        self.replace_future_types(&mut i.sig, Some(&mut i.block));
+        visit_item_fn_mut(self, i);
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, i: &mut ImplItemFn) {
+        self.replace_future_types(&mut i.sig, Some(&mut i.block));
+        visit_impl_item_fn_mut(self, i);
+    }
+
+    fn visit_trait_item_fn_mut(&mut self, i: &mut TraitItemFn) {
+        self.replace_future_types(&mut i.sig, i.default.as_mut());
+        visit_trait_item_fn_mut(self, i);
+    }
+
+    fn visit_item_trait_mut(&mut self, i: &mut ItemTrait) {
+        // associated types: `type Fut: Future<Output = Self::Item>;`
+        let mut outputs = vec![];
+        i.items.retain(|item| match item {
+            TraitItem::Type(assoc) => match assoc.bounds.iter().find_map(future_output_type) {
+                Some(output) => {
+                    outputs.push((assoc.ident.clone(), output));
+                    false
+                }
+                None => true,
+            },
+            _ => true,
+        });
+        for item in &mut i.items {
+            if let TraitItem::Type(assoc) = item {
+                for bound in &mut assoc.bounds {
+                    if let Some(item_type) = stream_item_type(bound) {
+                        *bound = parse_quote!(::core::iter::Iterator<Item = #item_type>);
                     }
                 }
             }
+        }
+        for (name, output) in &outputs {
+            ReplaceSelfProjection::new(name, output).visit_item_trait_mut(i);
+        }
+        visit_item_trait_mut(self, i);
+    }
+
+    fn visit_item_impl_mut(&mut self, i: &mut ItemImpl) {
+        // associated types: `type Fut = Pin<Box<dyn Future<Output = u8>>>;`
+        let mut outputs = vec![];
+        i.items.retain(|item| match item {
+            ImplItem::Type(assoc) => match future_type_output(&assoc.ty) {
+                Some(output) => {
+                    outputs.push((assoc.ident.clone(), output));
+                    false
+                }
+                None => true,
+            },
+            _ => true,
+        });
+        for item in &mut i.items {
+            if let ImplItem::Type(assoc) = item {
+                if let Some(iter) = stream_type_to_iterator(&assoc.ty) {
+                    assoc.ty = iter;
+                }
+            }
+        }
+        for (name, output) in &outputs {
+            ReplaceSelfProjection::new(name, output).visit_item_impl_mut(i);
+        }
+        visit_item_impl_mut(self, i);
+    }
+}
+
+/// Remove all `.await` in a token stream
+fn remove_await_tokens(tokens: TokenStream) -> TokenStream {
+    let mut output = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '.' => match tokens.peek() {
+                Some(TokenTree::Ident(ident)) if ident == "await" => {
+                    tokens.next();
+                }
+                _ => output.extend(Some(TokenTree::Punct(punct))),
+            },
+            TokenTree::Group(group) => {
+                let mut new_group =
+                    Group::new(group.delimiter(), remove_await_tokens(group.stream()));
+                new_group.set_span(group.span());
+                output.extend(Some(TokenTree::Group(new_group)));
+            }
+            token => output.extend(Some(token)),
+        }
+    }
+    output
+}
+
+/// The async block inside `Box::pin(async {})` or `async {}.boxed()`
+fn boxed_async_block(expr: &Expr) -> Option<Expr> {
+    match expr {
+        Expr::Call(call) => {
+            let func = match &*call.func {
+                Expr::Path(func) => func,
+                _ => return None,
+            };
+            let segments = &func.path.segments;
+            let is_box_pin = segments.len() >= 2
+                && segments[segments.len() - 2].ident == "Box"
+                && segments[segments.len() - 1].ident == "pin";
+            match call.args.first() {
+                Some(arg @ Expr::Async(_)) if is_box_pin && call.args.len() == 1 => {
+                    Some(arg.clone())
+                }
+                _ => None,
+            }
+        }
+        Expr::MethodCall(call)
+            if (call.method == "boxed" || call.method == "boxed_local")
+                && call.args.is_empty()
+                && matches!(*call.receiver, Expr::Async(_)) =>
+        {
+            Some((*call.receiver).clone())
+        }
+        _ => None,
+    }
+}
 
-            if let Some(where_clause) = &item_fn.sig.generics.where_clause {
-                for predicate in &where_clause.predicates {
-                    if let WherePredicate::Type(predicate_type) = predicate {
-                        let generic_type_name = if let Type::Path(p) = &predicate_type.bounded_ty {
-                            p.path.segments[0].ident.to_string()
-                        } else {
-                            panic!("Please submit an issue");
-                        };
+/// Find generic parameters and `impl Trait` arguments bounded by `Future`
+/// like traits, and replace them with their output type
+fn replace_future_types(sig: &mut Signature, mut block: Option<&mut Block>) -> Result<()> {
+    let mut inputs: Vec<(String, Type)> = vec![];
+    let mut errors: Option<Error> = None;
 
-                        for bound in &predicate_type.bounds {
-                            inputs.extend(search_trait_bound(&generic_type_name, bound));
+    let generic_type_names = sig
+        .generics
+        .type_params()
+        .map(|type_param| type_param.ident.to_string())
+        .collect::<Vec<_>>();
+
+    // generic params: <T:Future<Output=()>, F>
+    for type_param in sig.generics.type_params() {
+        let generic_type_name = type_param.ident.to_string();
+
+        // bound: Future<Output=()>
+        for bound in &type_param.bounds {
+            inputs.extend(search_trait_bound(&generic_type_name, bound));
+        }
+    }
+
+    if let Some(where_clause) = &sig.generics.where_clause {
+        for predicate in &where_clause.predicates {
+            if let WherePredicate::Type(predicate_type) = predicate {
+                let generic_type_name = generic_type_names
+                    .iter()
+                    .find(|name| is_generic_type(&predicate_type.bounded_ty, name));
+
+                for bound in &predicate_type.bounds {
+                    match generic_type_name {
+                        Some(name) => inputs.extend(search_trait_bound(name, bound)),
+                        // only generic parameters of this function can be replaced
+                        None if !matches!(predicate_type.bounded_ty, Type::Path(_))
+                            && future_output_type(bound).is_some() =>
+                        {
+                            let err = Error::new_spanned(
+                                &predicate_type.bounded_ty,
+                                "Cannot convert this `Future` bound to sync, bound a generic \
+                                 type parameter of the function instead",
+                            );
+                            push_error(&mut errors, err);
                         }
+                        None => {}
                     }
                 }
             }
+        }
+    }
+
+    // the same generic type must always resolve to the same output
+    for (i, (name, output)) in inputs.iter().enumerate() {
+        let conflict = inputs[..i].iter().any(|(prev_name, prev_output)| {
+            prev_name == name && quote!(#prev_output).to_string() != quote!(#output).to_string()
+        });
+        if conflict {
+            let msg = format!("Conflicting `Future` output types for `{}`", name);
+            push_error(&mut errors, Error::new(output.span(), msg));
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
 
-            for (generic_type_name, path_seg) in &inputs {
-                ReplaceGenericType::replace_generic_type(i, generic_type_name, path_seg);
+    for (generic_type_name, output) in &inputs {
+        ReplaceGenericType::replace_generic_type(
+            sig,
+            block.as_deref_mut(),
+            generic_type_name,
+            output,
+        );
+    }
+
+    // argument position: `fut: impl Future<Output=()>`
+    for input in &mut sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            if let Type::ImplTrait(impl_trait) = &*pat_type.ty {
+                if let Some(output) = impl_trait.bounds.iter().find_map(future_output_type) {
+                    *pat_type.ty = output;
+                }
             }
         }
-        visit_item_mut(self, i);
     }
+    Ok(())
 }
 
-fn search_trait_bound(
-    generic_type_name: &str,
-    bound: &TypeParamBound,
-) -> Vec<(String, PathSegment)> {
-    let mut inputs = vec![];
+fn push_error(errors: &mut Option<Error>, err: Error) {
+    match errors {
+        Some(errors) => errors.combine(err),
+        None => *errors = Some(err),
+    }
+}
 
-    if let TypeParamBound::Trait(trait_bound) = bound {
-        let segment = &trait_bound.path.segments[trait_bound.path.segments.len() - 1];
-        let name = segment.ident.to_string();
-        if name.eq("Future") {
-            // match Future<Output=Type>
-            if let PathArguments::AngleBracketed(args) = &segment.arguments {
-                // binding: Output=Type
-                if let GenericArgument::Binding(binding) = &args.args[0] {
-                    if let Type::Path(p) = &binding.ty {
-                        inputs.push((generic_type_name.to_owned(), p.path.segments[0].clone()));
+fn search_trait_bound(generic_type_name: &str, bound: &TypeParamBound) -> Vec<(String, Type)> {
+    future_output_type(bound)
+        .map(|output| (generic_type_name.to_owned(), output))
+        .into_iter()
+        .collect()
+}
+
+/// Output type of a `Future<Output=T>`, `IntoFuture<Output=T>` or
+/// `TryFuture<Ok=T, Error=E>` bound
+fn future_output_type(bound: &TypeParamBound) -> Option<Type> {
+    let trait_bound = match bound {
+        TypeParamBound::Trait(trait_bound) => trait_bound,
+        _ => return None,
+    };
+    let segment = trait_bound.path.segments.last()?;
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args,
+        _ => return None,
+    };
+    // binding: Output=Type
+    let binding = |name: &str| {
+        args.args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(binding) if binding.ident == name => Some(&binding.ty),
+            _ => None,
+        })
+    };
+
+    match segment.ident.to_string().as_str() {
+        "Future" | "IntoFuture" => binding("Output").cloned(),
+        "TryFuture" => {
+            let ok = binding("Ok")?;
+            let err = binding("Error")?;
+            Some(parse_quote!(::core::result::Result<#ok, #err>))
+        }
+        _ => None,
+    }
+}
+
+/// Item type of a `Stream<Item=T>` bound
+fn stream_item_type(bound: &TypeParamBound) -> Option<Type> {
+    let trait_bound = match bound {
+        TypeParamBound::Trait(trait_bound) => trait_bound,
+        _ => return None,
+    };
+    let segment = trait_bound.path.segments.last()?;
+    if segment.ident != "Stream" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::AssocType(binding) if binding.ident == "Item" => {
+                Some(binding.ty.clone())
+            }
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Generic type arguments of the last segment of a type path
+fn type_arguments(ty: &TypePath) -> Vec<&Type> {
+    match ty.path.segments.last().map(|segment| &segment.arguments) {
+        Some(PathArguments::AngleBracketed(args)) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Output type of a concrete future type, like `BoxFuture<'a, T>` or
+/// `Pin<Box<dyn Future<Output = T> + Send>>`
+fn future_type_output(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::TraitObject(TypeTraitObject { bounds, .. })
+        | Type::ImplTrait(TypeImplTrait { bounds, .. }) => {
+            bounds.iter().find_map(future_output_type)
+        }
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            future_type_output(elem)
+        }
+        Type::Path(p) if p.qself.is_none() => {
+            let name = p.path.segments.last()?.ident.to_string();
+            match name.as_str() {
+                "BoxFuture" | "LocalBoxFuture" => type_arguments(p).last().cloned().cloned(),
+                "Pin" | "Box" => future_type_output(type_arguments(p).first()?),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Iterator equivalent of a concrete stream type, like `BoxStream<'a, T>` or
+/// `Pin<Box<dyn Stream<Item = T> + Send>>`
+fn stream_type_to_iterator(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            stream_type_to_iterator(elem)
+        }
+        Type::Path(p) if p.qself.is_none() => {
+            let segment = p.path.segments.last()?;
+            match segment.ident.to_string().as_str() {
+                "BoxStream" | "LocalBoxStream" => {
+                    let item_type = type_arguments(p).last().cloned()?;
+                    let lifetimes = match &segment.arguments {
+                        PathArguments::AngleBracketed(args) => args
+                            .args
+                            .iter()
+                            .filter_map(|arg| match arg {
+                                GenericArgument::Lifetime(lifetime) => Some(lifetime),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => vec![],
+                    };
+                    let send = if segment.ident == "BoxStream" {
+                        Some(quote!(+ ::core::marker::Send))
+                    } else {
+                        None
+                    };
+                    Some(parse_quote!(
+                        ::std::boxed::Box<
+                            dyn ::core::iter::Iterator<Item = #item_type> #send #(+ #lifetimes)*
+                        >
+                    ))
+                }
+                "Pin" | "Box" => {
+                    let inner = type_arguments(p).first().cloned()?;
+                    let mut object = match inner {
+                        Type::TraitObject(object) => object.clone(),
+                        inner => return stream_type_to_iterator(inner),
+                    };
+                    let mut found = false;
+                    for bound in &mut object.bounds {
+                        if let Some(item_type) = stream_item_type(bound) {
+                            *bound = parse_quote!(::core::iter::Iterator<Item = #item_type>);
+                            found = true;
+                        }
+                    }
+                    if found {
+                        Some(parse_quote!(::std::boxed::Box<#object>))
+                    } else {
+                        None
                     }
                 }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Resolve the code of one version: nested items, fields, variants and
+/// statements marked with `#[sync_only]` or `#[async_only]` are only kept in
+/// their own version, `#[sync_type(..)]`, `#[sync_attr(..)]` and their async
+/// twins are applied or dropped, and `select_mode!` is replaced with the
+/// matching arm
+pub struct SelectMode<'a> {
+    is_sync: bool,
+    /// Path of this crate, for `maybe_async::sync_only` and the others
+    krate: &'a Path,
+    errors: Vec<Error>,
+}
+
+impl<'a> SelectMode<'a> {
+    pub fn new(is_sync: bool, krate: &'a Path) -> Self {
+        Self {
+            is_sync,
+            krate,
+            errors: vec![],
+        }
+    }
+
+    /// Resolve the code of this version in `input`, returns the errors of
+    /// malformed `sync_attr`/`async_attr`
+    pub fn select(mut self, input: &mut parse::Item) -> Vec<Error> {
+        self.select_attrs(input.attrs_mut());
+        input.visit_mut(&mut self);
+        self.errors
+    }
+
+    /// The name of this version, then the name of the other one
+    fn names(&self, sync: &'static str, async_: &'static str) -> (&'static str, &'static str) {
+        if self.is_sync {
+            (sync, async_)
+        } else {
+            (async_, sync)
+        }
+    }
+
+    /// Expand `#[sync_attr(..)]` or `#[async_attr(..)]` of this version into
+    /// the attributes inside, like `cfg_attr`, and `#[doc_sync = ".."]` or
+    /// `#[doc_async = ".."]` into docs, and remove the ones of the other
+    /// version
+    fn select_attrs(&mut self, attrs: &mut Vec<Attribute>) {
+        let (keep, drop) = self.names("sync_attr", "async_attr");
+        let (keep_doc, drop_doc) = self.names("doc_sync", "doc_async");
+        *attrs = std::mem::take(attrs)
+            .into_iter()
+            .flat_map(|attr| {
+                if is_maybe_async_path(attr.path(), self.krate, keep) {
+                    match attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+                        Ok(metas) => metas
+                            .into_iter()
+                            .map(|meta| parse_quote!(#[#meta]))
+                            .collect(),
+                        Err(err) => {
+                            self.errors.push(err);
+                            vec![]
+                        }
+                    }
+                } else if is_maybe_async_path(attr.path(), self.krate, keep_doc) {
+                    match &attr.meta {
+                        Meta::NameValue(meta) => {
+                            let doc = &meta.value;
+                            vec![parse_quote!(#[doc = #doc])]
+                        }
+                        _ => {
+                            self.errors.push(Error::new_spanned(
+                                &attr,
+                                format!("expected `#[{} = \"...\"]`", keep_doc),
+                            ));
+                            vec![]
+                        }
+                    }
+                } else if is_maybe_async_path(attr.path(), self.krate, drop)
+                    || is_maybe_async_path(attr.path(), self.krate, drop_doc)
+                {
+                    vec![]
+                } else {
+                    vec![attr]
+                }
+            })
+            .collect();
+    }
+
+    /// Whether the node with `attrs` is kept, removes the marker if so
+    fn retain(&self, attrs: Option<&mut Vec<Attribute>>) -> bool {
+        let Some(attrs) = attrs else {
+            return true;
+        };
+        let (keep, drop) = self.names("sync_only", "async_only");
+        if attrs
+            .iter()
+            .any(|attr| is_maybe_async_path(attr.path(), self.krate, drop))
+        {
+            return false;
+        }
+        attrs.retain(|attr| !is_maybe_async_path(attr.path(), self.krate, keep));
+        true
+    }
+
+    /// Replace `ty` with the type of `#[sync_type(..)]` or `#[async_type(..)]`
+    /// of this version if any, and remove both attributes
+    fn select_type(&self, attrs: &mut Vec<Attribute>, ty: &mut Type) {
+        let (keep, drop) = self.names("sync_type", "async_type");
+        let mut selected = None;
+        attrs.retain(|attr| {
+            if is_maybe_async_path(attr.path(), self.krate, keep) {
+                selected = Some(attr.parse_args::<Type>());
+                false
+            } else {
+                !is_maybe_async_path(attr.path(), self.krate, drop)
+            }
+        });
+        match selected {
+            Some(Ok(selected)) => *ty = selected,
+            Some(Err(err)) => *ty = Type::Verbatim(err.to_compile_error()),
+            None => {}
+        }
+    }
+
+    fn retain_fields(&self, fields: &mut Punctuated<Field, Token![,]>) {
+        *fields = std::mem::take(fields)
+            .into_pairs()
+            .filter_map(|mut pair| {
+                self.retain(Some(&mut pair.value_mut().attrs))
+                    .then_some(pair)
+            })
+            .collect();
+    }
+
+    /// The arm of a `select_mode!` for this version, `None` for other macros
+    fn select_arm(&self, mac: &Macro) -> Option<Result<Option<ModeArm>>> {
+        if !is_maybe_async_path(&mac.path, self.krate, "select_mode") {
+            return None;
+        }
+        Some(mac.parse_body::<SelectModeArms>().map(|arms| {
+            if self.is_sync {
+                arms.sync
+            } else {
+                arms.async_
+            }
+        }))
+    }
+
+    /// Statements replacing a `select_mode!` statement
+    fn select_stmts(&self, mac: &Macro, semi: Option<Token![;]>, last: bool) -> Option<Vec<Stmt>> {
+        let stmts = match self.select_arm(mac)? {
+            Ok(None) => vec![],
+            Ok(Some(ModeArm::Braced(tokens))) => Block::parse_within
+                .parse2(tokens)
+                .unwrap_or_else(|err| vec![error_stmt(err)]),
+            // keep statements apart when the macro was not followed by `;`
+            Ok(Some(ModeArm::Expr(expr))) => {
+                vec![Stmt::Expr(expr, semi.or((!last).then(Default::default)))]
+            }
+            Err(err) => vec![error_stmt(err)],
+        };
+        Some(stmts)
+    }
+
+    /// Items replacing a `select_mode!` in a trait or an impl
+    fn select_items<T: Parse>(&self, mac: &Macro, error: fn(TokenStream) -> T) -> Option<Vec<T>> {
+        let items = match self.select_arm(mac)? {
+            Ok(None) => Ok(vec![]),
+            Ok(Some(ModeArm::Braced(tokens))) => {
+                let parser = |input: ParseStream| {
+                    let mut items = vec![];
+                    while !input.is_empty() {
+                        items.push(input.parse()?);
+                    }
+                    Ok(items)
+                };
+                parser.parse2(tokens)
+            }
+            Ok(Some(ModeArm::Expr(expr))) => Err(Error::new_spanned(
+                expr,
+                "expected items in braces, like `sync => { fn f() {} }`",
+            )),
+            Err(err) => Err(err),
+        };
+        Some(items.unwrap_or_else(|err| vec![error(err.to_compile_error())]))
+    }
+}
+
+impl VisitMut for SelectMode<'_> {
+    fn visit_item_mut(&mut self, i: &mut Item) {
+        if let Some(attrs) = item_attrs(i) {
+            self.select_attrs(attrs);
+        }
+        visit_mut::visit_item_mut(self, i);
+    }
+
+    fn visit_impl_item_mut(&mut self, i: &mut ImplItem) {
+        if let Some(attrs) = impl_item_attrs(i) {
+            self.select_attrs(attrs);
+        }
+        visit_mut::visit_impl_item_mut(self, i);
+    }
+
+    fn visit_trait_item_mut(&mut self, i: &mut TraitItem) {
+        if let Some(attrs) = trait_item_attrs(i) {
+            self.select_attrs(attrs);
+        }
+        visit_mut::visit_trait_item_mut(self, i);
+    }
+
+    fn visit_variant_mut(&mut self, i: &mut Variant) {
+        self.select_attrs(&mut i.attrs);
+        visit_mut::visit_variant_mut(self, i);
+    }
+
+    fn visit_local_mut(&mut self, i: &mut Local) {
+        self.select_attrs(&mut i.attrs);
+        visit_mut::visit_local_mut(self, i);
+    }
+
+    fn visit_item_trait_mut(&mut self, i: &mut ItemTrait) {
+        i.items
+            .retain_mut(|item| self.retain(trait_item_attrs(item)));
+        i.items = std::mem::take(&mut i.items)
+            .into_iter()
+            .flat_map(|item| match &item {
+                TraitItem::Macro(mac) => self
+                    .select_items(&mac.mac, TraitItem::Verbatim)
+                    .unwrap_or_else(|| vec![item]),
+                _ => vec![item],
+            })
+            .collect();
+        visit_item_trait_mut(self, i);
+    }
+
+    fn visit_item_impl_mut(&mut self, i: &mut ItemImpl) {
+        i.items
+            .retain_mut(|item| self.retain(impl_item_attrs(item)));
+        i.items = std::mem::take(&mut i.items)
+            .into_iter()
+            .flat_map(|item| match &item {
+                ImplItem::Macro(mac) => self
+                    .select_items(&mac.mac, ImplItem::Verbatim)
+                    .unwrap_or_else(|| vec![item]),
+                _ => vec![item],
+            })
+            .collect();
+        visit_item_impl_mut(self, i);
+    }
+
+    fn visit_field_mut(&mut self, i: &mut Field) {
+        self.select_attrs(&mut i.attrs);
+        self.select_type(&mut i.attrs, &mut i.ty);
+        visit_mut::visit_field_mut(self, i);
+    }
+
+    fn visit_item_type_mut(&mut self, i: &mut ItemType) {
+        self.select_type(&mut i.attrs, &mut i.ty);
+        visit_mut::visit_item_type_mut(self, i);
+    }
+
+    fn visit_impl_item_type_mut(&mut self, i: &mut ImplItemType) {
+        self.select_type(&mut i.attrs, &mut i.ty);
+        visit_mut::visit_impl_item_type_mut(self, i);
+    }
+
+    fn visit_fields_named_mut(&mut self, i: &mut FieldsNamed) {
+        self.retain_fields(&mut i.named);
+        visit_mut::visit_fields_named_mut(self, i);
+    }
+
+    fn visit_fields_unnamed_mut(&mut self, i: &mut FieldsUnnamed) {
+        self.retain_fields(&mut i.unnamed);
+        visit_mut::visit_fields_unnamed_mut(self, i);
+    }
+
+    fn visit_item_enum_mut(&mut self, i: &mut ItemEnum) {
+        i.variants = std::mem::take(&mut i.variants)
+            .into_pairs()
+            .filter_map(|mut pair| {
+                self.retain(Some(&mut pair.value_mut().attrs))
+                    .then_some(pair)
+            })
+            .collect();
+        visit_mut::visit_item_enum_mut(self, i);
+    }
+
+    fn visit_block_mut(&mut self, i: &mut Block) {
+        i.stmts.retain_mut(|stmt| self.retain(stmt_attrs(stmt)));
+        let count = i.stmts.len();
+        i.stmts = std::mem::take(&mut i.stmts)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, stmt)| {
+                let last = index + 1 == count;
+                let stmts = match &stmt {
+                    Stmt::Macro(mac) => self.select_stmts(&mac.mac, mac.semi_token, last),
+                    Stmt::Expr(Expr::Macro(mac), semi) => self.select_stmts(&mac.mac, *semi, last),
+                    _ => None,
+                };
+                stmts.unwrap_or_else(|| vec![stmt])
+            })
+            .collect();
+        visit_mut::visit_block_mut(self, i);
+    }
+
+    fn visit_expr_mut(&mut self, i: &mut Expr) {
+        if let Expr::Macro(mac) = i {
+            if is_maybe_async_path(&mac.mac.path, self.krate, "is_async") {
+                let is_async = !self.is_sync;
+                *i = match mac.mac.parse_body::<Nothing>() {
+                    Ok(_) => parse_quote!(#is_async),
+                    Err(err) => error_expr(err),
+                };
+                return;
+            }
+            if let Some(arm) = self.select_arm(&mac.mac) {
+                *i = match arm {
+                    Ok(None) => parse_quote!(()),
+                    Ok(Some(ModeArm::Braced(tokens))) => {
+                        syn::parse2(quote!({ #tokens })).unwrap_or_else(error_expr)
+                    }
+                    // keep the precedence of the arm, like `2 * select_mode!(sync => a + b)`
+                    Ok(Some(ModeArm::Expr(expr))) => match expr {
+                        Expr::Binary(_)
+                        | Expr::Unary(_)
+                        | Expr::Cast(_)
+                        | Expr::Range(_)
+                        | Expr::Assign(_)
+                        | Expr::Closure(_)
+                        | Expr::Reference(_)
+                        | Expr::RawAddr(_)
+                        | Expr::Let(_)
+                        | Expr::Return(_)
+                        | Expr::Break(_)
+                        | Expr::Yield(_) => parse_quote!((#expr)),
+                        expr => expr,
+                    },
+                    Err(err) => error_expr(err),
+                };
+            }
+        }
+        visit_mut::visit_expr_mut(self, i);
+    }
+
+    // `is_async!()` and `select_mode!` are often used in the arguments of
+    // other macros, like `format!("{}", is_async!())`, which are only tokens
+    fn visit_macro_mut(&mut self, i: &mut Macro) {
+        let is_expr_macro = i
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| EXPR_MACROS.contains(&segment.ident.to_string().as_str()));
+        if is_expr_macro {
+            if let Ok(mut args) = i.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            {
+                for arg in &mut args {
+                    self.visit_expr_mut(arg);
+                }
+                i.tokens = quote!(#args);
+                return;
+            }
+        }
+        // unknown macros only get `is_async!()` replaced
+        i.tokens = replace_is_async_tokens(i.tokens.clone(), self.krate, !self.is_sync);
+    }
+}
+
+/// Replace `is_async!()` and `krate::is_async!()` in `tokens` with `is_async`
+fn replace_is_async_tokens(tokens: TokenStream, krate: &Path, is_async: bool) -> TokenStream {
+    let mut tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut index = 0;
+    while index < tokens.len() {
+        if let TokenTree::Group(group) = &tokens[index] {
+            let mut replaced = Group::new(
+                group.delimiter(),
+                replace_is_async_tokens(group.stream(), krate, is_async),
+            );
+            replaced.set_span(group.span());
+            tokens[index] = TokenTree::Group(replaced);
+        }
+        let is_call = match &tokens[index..] {
+            [TokenTree::Ident(ident), TokenTree::Punct(bang), TokenTree::Group(args), ..] => {
+                ident == "is_async" && bang.as_char() == '!' && args.stream().is_empty()
+            }
+            _ => false,
+        };
+        if is_call {
+            let mut start = index;
+            // include a leading `krate::` or `::krate::`, an ident and `::` per
+            // segment
+            let prefix = krate.segments.len() * 3;
+            if index >= prefix
+                && tokens[index - prefix..index]
+                    .chunks(3)
+                    .zip(&krate.segments)
+                    .all(|(chunk, segment)| match chunk {
+                        [TokenTree::Ident(ident), sep @ ..] => {
+                            *ident == segment.ident && is_path_sep(sep)
+                        }
+                        _ => false,
+                    })
+            {
+                start = index - prefix;
+                if start >= 2 && is_path_sep(&tokens[start - 2..start]) {
+                    start -= 2;
+                }
+            }
+            let lit = Ident::new(&is_async.to_string(), tokens[index].span());
+            tokens.splice(start..index + 3, [TokenTree::Ident(lit)]);
+            index = start;
+        }
+        index += 1;
+    }
+    tokens.into_iter().collect()
+}
+
+fn is_path_sep(tokens: &[TokenTree]) -> bool {
+    matches!(
+        tokens,
+        [TokenTree::Punct(first), TokenTree::Punct(second)]
+            if first.as_char() == ':' && second.as_char() == ':'
+    )
+}
+
+fn error_expr(err: Error) -> Expr {
+    Expr::Verbatim(err.to_compile_error())
+}
+
+fn error_stmt(err: Error) -> Stmt {
+    Stmt::Item(Item::Verbatim(err.to_compile_error()))
+}
+
+/// Matches `name` and `krate::name`, like `maybe_async::name`
+fn is_maybe_async_path(path: &Path, krate: &Path, name: &str) -> bool {
+    let Some(last) = path.segments.last() else {
+        return false;
+    };
+    last.ident == name
+        && (path.segments.len() == 1
+            || path.segments.len() == krate.segments.len() + 1
+                && path
+                    .segments
+                    .iter()
+                    .zip(&krate.segments)
+                    .all(|(segment, krate)| segment.ident == krate.ident))
+}
+
+fn trait_item_attrs(item: &mut TraitItem) -> Option<&mut Vec<Attribute>> {
+    match item {
+        TraitItem::Const(item) => Some(&mut item.attrs),
+        TraitItem::Fn(item) => Some(&mut item.attrs),
+        TraitItem::Type(item) => Some(&mut item.attrs),
+        TraitItem::Macro(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn impl_item_attrs(item: &mut ImplItem) -> Option<&mut Vec<Attribute>> {
+    match item {
+        ImplItem::Const(item) => Some(&mut item.attrs),
+        ImplItem::Fn(item) => Some(&mut item.attrs),
+        ImplItem::Type(item) => Some(&mut item.attrs),
+        ImplItem::Macro(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn stmt_attrs(stmt: &mut Stmt) -> Option<&mut Vec<Attribute>> {
+    match stmt {
+        Stmt::Local(local) => Some(&mut local.attrs),
+        Stmt::Macro(mac) => Some(&mut mac.attrs),
+        Stmt::Item(item) => item_attrs(item),
+        Stmt::Expr(expr, _) => expr_attrs(expr),
+    }
+}
+
+fn item_attrs(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    macro_rules! attrs {
+        ($($variant:ident)*) => {
+            match item {
+                $(Item::$variant(item) => Some(&mut item.attrs),)*
+                _ => None,
+            }
+        };
+    }
+    attrs!(Const Enum ExternCrate Fn ForeignMod Impl Macro Mod Static Struct Trait TraitAlias Type Union Use)
+}
+
+fn expr_attrs(expr: &mut Expr) -> Option<&mut Vec<Attribute>> {
+    macro_rules! attrs {
+        ($($variant:ident)*) => {
+            match expr {
+                $(Expr::$variant(expr) => Some(&mut expr.attrs),)*
+                _ => None,
+            }
+        };
+    }
+    attrs!(
+        Array Assign Async Await Binary Block Break Call Cast Closure Const Continue Field ForLoop
+        Group If Index Infer Let Lit Loop Macro Match MethodCall Paren Path Range RawAddr Reference
+        Repeat Return Struct Try TryBlock Tuple Unary Unsafe While Yield
+    )
+}
+
+/// Rename references to dual mode types, like `Inner` to `InnerSync`, in
+/// types, bounds, supertraits, trait paths of impls and expressions
+pub struct RenameTypes<'a> {
+    names: &'a [String],
+    suffix: &'a str,
+    /// Name of the crate defining the types, which doc examples use in paths
+    /// like `my_crate::Client`
+    crate_name: Option<String>,
+}
+
+impl<'a> RenameTypes<'a> {
+    pub fn new(names: &'a [String], suffix: &'a str) -> Self {
+        Self {
+            names,
+            suffix,
+            crate_name: None,
+        }
+    }
+
+    pub fn with_crate_name(mut self, crate_name: Option<String>) -> Self {
+        self.crate_name = crate_name;
+        self
+    }
+
+    /// Whether `path` starts in this crate, like `crate::client::Client` or
+    /// `Self::Client`, where the dual mode types are looked for after the
+    /// first segment
+    fn is_local(&self, path: &Path) -> bool {
+        let Some(first) = path.segments.first() else {
+            return false;
+        };
+        let is_crate_name = self
+            .crate_name
+            .as_ref()
+            .is_some_and(|name| first.ident == name);
+        (path.leading_colon.is_none()
+            && (first.ident == "crate"
+                || first.ident == "self"
+                || first.ident == "super"
+                || first.ident == "Self"))
+            || is_crate_name
+    }
+}
+
+impl<'a> VisitMut for RenameTypes<'a> {
+    fn visit_path_mut(&mut self, i: &mut Path) {
+        // `Client` or `crate::client::Client` name a dual mode type, not
+        // `other::Client` of another module or crate, and a segment after a
+        // path, like in `Role::Client`, `Inner::new` or `Event::Inner`, is an
+        // associated item or a variant
+        let is_local = self.is_local(i);
+        for (index, segment) in i.segments.iter_mut().enumerate() {
+            if index > 0 && !is_local {
+                break;
+            }
+            if self.names.iter().any(|name| segment.ident == name) {
+                segment.ident = Ident::new(
+                    &format!("{}{}", segment.ident, self.suffix),
+                    segment.ident.span(),
+                );
+                break;
+            }
+            if segment.ident != "Self" && segment.ident.to_string().starts_with(char::is_uppercase)
+            {
+                break;
+            }
+        }
+        visit_mut::visit_path_mut(self, i);
+    }
+}
+
+/// Attribute macros of async runtimes and helpers, which fail on the sync
+/// version
+const ASYNC_ONLY_ATTRS: &[&str] = &[
+    "async_trait",
+    "async_trait::async_trait",
+    "tokio::main",
+    "tokio::test",
+    "async_std::main",
+    "async_std::test",
+    "async_recursion",
+    "async_recursion::async_recursion",
+];
+
+/// Remove async only attribute macros, like `#[tokio::main]` or
+/// `#[async_trait]`, from an item converted to sync and its nested items
+pub struct RemoveAsyncAttrs<'a> {
+    extra: &'a [Path],
+}
+
+impl<'a> RemoveAsyncAttrs<'a> {
+    pub fn new(extra: &'a [Path]) -> Self {
+        Self { extra }
+    }
+
+    pub fn remove(&mut self, input: &mut parse::Item) {
+        self.retain(input.attrs_mut());
+        input.visit_mut(self);
+    }
+
+    fn retain(&self, attrs: &mut Vec<Attribute>) {
+        attrs.retain(|attr| {
+            let name = path_to_string(attr.path());
+            !ASYNC_ONLY_ATTRS.contains(&name.as_str())
+                && !self.extra.iter().any(|path| path_to_string(path) == name)
+        });
+    }
+}
+
+impl<'a> VisitMut for RemoveAsyncAttrs<'a> {
+    fn visit_item_mut(&mut self, i: &mut Item) {
+        if let Some(attrs) = item_attrs(i) {
+            self.retain(attrs);
+        }
+        visit_mut::visit_item_mut(self, i);
+    }
+
+    fn visit_impl_item_mut(&mut self, i: &mut ImplItem) {
+        if let Some(attrs) = impl_item_attrs(i) {
+            self.retain(attrs);
+        }
+        visit_mut::visit_impl_item_mut(self, i);
+    }
+
+    fn visit_trait_item_mut(&mut self, i: &mut TraitItem) {
+        if let Some(attrs) = trait_item_attrs(i) {
+            self.retain(attrs);
+        }
+        visit_mut::visit_trait_item_mut(self, i);
+    }
+}
+
+/// Keep the first `#[async_trait]` of `attrs`, returns whether there is one
+pub fn dedup_async_trait(attrs: &mut Vec<Attribute>) -> bool {
+    let mut found = false;
+    attrs.retain(|attr| {
+        let name = path_to_string(attr.path());
+        if name != "async_trait" && name != "async_trait::async_trait" {
+            return true;
+        }
+        !std::mem::replace(&mut found, true)
+    });
+    found
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Rename awaited calls of the dual mode functions listed by `fns(...)` in a
+/// test to the function of the version, like `fetch(url).await` to
+/// `fetch_sync(url).await`, and dual mode types in the arguments of macros
+/// like `assert_eq!`
+pub struct RenameAwaitedCalls<'a> {
+    types: &'a [String],
+    fns: &'a [String],
+    fn_suffix: &'a str,
+    type_suffix: &'a str,
+}
+
+impl<'a> RenameAwaitedCalls<'a> {
+    pub fn new(
+        types: &'a [String],
+        fns: &'a [String],
+        fn_suffix: &'a str,
+        type_suffix: &'a str,
+    ) -> Self {
+        Self {
+            types,
+            fns,
+            fn_suffix,
+            type_suffix,
+        }
+    }
+}
+
+impl<'a> VisitMut for RenameAwaitedCalls<'a> {
+    fn visit_expr_await_mut(&mut self, i: &mut ExprAwait) {
+        if let Expr::Call(call) = &mut *i.base {
+            if let Expr::Path(func) = &mut *call.func {
+                let path = &mut func.path;
+                // `fetch` and `crate::api::fetch` may be the listed functions,
+                // `tokio::fs::read` is in another crate and keeps its name even
+                // when a function of this crate has the same one
+                let is_local = path.leading_colon.is_none()
+                    && match path.segments.first() {
+                        Some(first) if path.segments.len() > 1 => {
+                            first.ident == "crate"
+                                || first.ident == "self"
+                                || first.ident == "super"
+                        }
+                        _ => true,
+                    };
+                if let Some(last) = path
+                    .segments
+                    .last_mut()
+                    .filter(|last| is_local && self.fns.iter().any(|name| last.ident == name))
+                {
+                    last.ident = Ident::new(
+                        &format!("{}{}", last.ident, self.fn_suffix),
+                        last.ident.span(),
+                    );
+                }
+            }
+        }
+        visit_mut::visit_expr_await_mut(self, i);
+    }
+
+    fn visit_macro_mut(&mut self, i: &mut Macro) {
+        let is_expr_macro = i
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| EXPR_MACROS.contains(&segment.ident.to_string().as_str()));
+        if !is_expr_macro {
+            return;
+        }
+        if let Ok(mut args) = i.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &mut args {
+                self.visit_expr_mut(arg);
+                RenameTypes::new(self.types, self.type_suffix).visit_expr_mut(arg);
             }
+            i.tokens = quote!(#args);
         }
     }
-    inputs
 }
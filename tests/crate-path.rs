@@ -0,0 +1,48 @@
+mod facade {
+    #[allow(unused_imports)]
+    pub use async_trait;
+    pub use maybe_async;
+}
+
+#[facade::maybe_async::both(crate = facade::maybe_async, async_trait = facade::async_trait)]
+trait Service {
+    async fn call(&self) -> bool;
+}
+
+#[facade::maybe_async::both(crate = facade::maybe_async, async_trait = facade::async_trait)]
+struct Echo;
+
+#[facade::maybe_async::both(
+    crate = facade::maybe_async,
+    async_trait = facade::async_trait,
+    types(Echo, Service)
+)]
+impl Service for Echo {
+    async fn call(&self) -> bool {
+        #[facade::maybe_async::sync_only]
+        let version = false;
+        #[facade::maybe_async::async_only]
+        let version = true;
+        let is_async = facade::maybe_async::is_async!();
+        assert_eq!(version, is_async);
+        is_async
+    }
+}
+
+#[maybe_async::test(
+    all(feature = "is_sync", not(feature = "is_async")),
+    async(feature = "is_async", tokio::test),
+    crate = facade::maybe_async
+)]
+async fn test_facade() {
+    #[facade::maybe_async::sync_only]
+    assert!(!EchoSync.call().await);
+    #[facade::maybe_async::async_only]
+    assert!(EchoAsync.call().await);
+}
+
+#[maybe_async::test(both, async(tokio), crate = facade::maybe_async, types(Echo, Service))]
+async fn test_facade_both() {
+    let is_async = facade::maybe_async::is_async!();
+    assert_eq!(Echo.call().await, is_async);
+}
@@ -0,0 +1,80 @@
+use std::{env, path::Path, process::Command};
+
+/// Run `cargo` with `args` on the crate in `tests/downstream`, returns its
+/// output
+fn cargo(args: &[&str]) -> String {
+    let output = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(args)
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/downstream/Cargo.toml"))
+        .env(
+            "CARGO_TARGET_DIR",
+            Path::new(env!("CARGO_TARGET_TMPDIR")).join("downstream"),
+        )
+        .output()
+        .expect("failed to run cargo");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr);
+    stdout.into_owned()
+}
+
+#[test]
+fn test_both_in_downstream_crate() {
+    let output = cargo(&["test", "--lib"]);
+    for test in [
+        "test_double_sync",
+        "test_double_async",
+        "test_double__futures_executor",
+    ] {
+        assert!(
+            output.contains(&format!("test tests::{} ... ok", test)),
+            "`{}` did not run:\n{}",
+            test,
+            output
+        );
+    }
+}
+
+#[test]
+fn test_bench_in_downstream_crate() {
+    let output = cargo(&["test", "--bench", "double"]);
+    for bench in ["double_two/sync", "double_two/async"] {
+        assert!(
+            output.contains(&format!("Testing {}\nSuccess", bench)),
+            "`{}` did not run:\n{}",
+            bench,
+            output
+        );
+    }
+}
+
+#[test]
+fn test_doc_examples_in_downstream_crate() {
+    let output = cargo(&["test", "--doc"]);
+    for example in [
+        "ClientSync (line",
+        "ClientAsync (line",
+        "ClientSync::connect (line",
+        "ClientAsync::connect (line",
+    ] {
+        assert!(
+            output.contains(&format!("src/lib.rs - {}", example)),
+            "the example of `{}` did not run:\n{}",
+            example.trim_end_matches(" (line"),
+            output
+        );
+    }
+}
+
+#[test]
+fn test_manifest_is_an_input_of_downstream_crate() {
+    cargo(&["build", "--lib"]);
+    let dep_info = Path::new(env!("CARGO_TARGET_TMPDIR")).join("downstream/debug/libdownstream.d");
+    let dep_info = std::fs::read_to_string(dep_info).expect("failed to read the dep-info");
+    assert!(
+        dep_info.contains("tests/downstream/Cargo.toml"),
+        "changes to the registered types would not rebuild the crate:\n{}",
+        dep_info
+    );
+}
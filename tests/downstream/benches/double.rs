@@ -0,0 +1,9 @@
+use downstream::*;
+
+#[maybe_async::bench(async = futures_executor, fns(double))]
+async fn double_two() -> u8 {
+    double(2).await
+}
+
+criterion::criterion_group!(benches, double_two);
+criterion::criterion_main!(benches);
@@ -0,0 +1,64 @@
+#[maybe_async::both]
+pub async fn double(n: u8) -> u8 {
+    n * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[maybe_async::test(both, async = tokio::test, matrix(futures_executor), fns(double))]
+    async fn test_double() {
+        assert_eq!(double(2).await, 4);
+    }
+}
+
+/// A client of an echo service
+///
+/// ```
+/// use downstream::Client;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), String> {
+/// let client = Client::connect("localhost").await?;
+/// // the reply ends with `.await` in both versions
+/// assert_eq!(client.send("ping").await?, "ping.await");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Only Rust examples are converted:
+///
+/// ```text
+/// client.send("ping").await?
+/// ```
+#[maybe_async::both]
+pub struct Client {
+    host: String,
+}
+
+#[maybe_async::both]
+impl Client {
+    /// Connect to `host`
+    ///
+    /// ```
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let client = downstream::Client::connect("localhost").await.unwrap();
+    /// assert_eq!(client.host(), "localhost");
+    /// # }
+    /// ```
+    pub async fn connect(host: &str) -> Result<Self, String> {
+        Ok(Self {
+            host: host.to_string(),
+        })
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub async fn send(&self, body: &str) -> Result<String, String> {
+        Ok(format!("{}.await", body))
+    }
+}
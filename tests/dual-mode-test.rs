@@ -0,0 +1,60 @@
+#[maybe_async::both]
+async fn double(n: u8) -> u8 {
+    n * 2
+}
+
+#[maybe_async::both]
+pub struct Counter {
+    count: u8,
+}
+
+#[maybe_async::both]
+impl Counter {
+    pub async fn start(count: u8) -> Self {
+        Self { count }
+    }
+
+    pub async fn add(&mut self, n: u8) -> u8 {
+        self.count += n;
+        self.count
+    }
+}
+
+#[maybe_async::test(both, async = tokio::test, fns(double))]
+async fn test_double() {
+    assert_eq!(double(2).await, 4);
+}
+
+#[maybe_async::test(both, async = async_std::test, types(Counter))]
+async fn test_counter() {
+    let mut counter = Counter::start(1).await;
+    assert_eq!(counter.add(2).await, 3);
+    let is_async = maybe_async::is_async!();
+    assert_eq!(Counter::IS_ASYNC, is_async);
+}
+
+mod nested {
+    #[maybe_async::both]
+    pub async fn triple(n: u8) -> u8 {
+        n * 3
+    }
+}
+
+#[maybe_async::test(both, async = tokio::test, fns(triple, double))]
+async fn test_calls_by_path() {
+    assert_eq!(crate::nested::triple(2).await, 6);
+    assert_eq!(self::double(2).await, 4);
+    #[maybe_async::async_only]
+    assert_eq!(std::future::ready(3).await, 3);
+    #[maybe_async::async_only]
+    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+}
+
+#[maybe_async::test(both, async = tokio::test, fns(double))]
+async fn test_imported_calls() {
+    #[maybe_async::async_only]
+    use std::future::ready;
+    #[maybe_async::async_only]
+    assert_eq!(ready(2).await, 2);
+    assert_eq!(double(1).await, 2);
+}
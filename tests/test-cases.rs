@@ -0,0 +1,30 @@
+#[maybe_async::both]
+async fn parse(s: &str) -> Result<u8, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[maybe_async::test(both, async(tokio), matrix(async_std, smol), fns(parse))]
+#[case("1", 1)]
+#[case("42", 42)]
+#[case::max("255", u8::MAX)]
+async fn test_parse(input: &str, expected: u8) {
+    assert_eq!(parse(input).await, Ok(expected));
+}
+
+#[maybe_async::test(both, async(tokio), fns(parse))]
+#[case::empty("")]
+#[case::overflow("256")]
+async fn test_parse_error(input: &str) -> Result<(), String> {
+    match parse(input).await {
+        Ok(n) => Err(format!("{} parsed to {}", input, n)),
+        Err(_) => Ok(()),
+    }
+}
+
+#[maybe_async::test(feature = "is_sync", async(not(feature = "is_sync"), tokio))]
+#[case((1, 2), 3)]
+#[case((2, 2), 4)]
+async fn test_add((a, b): (u8, u8), sum: u8) {
+    let total = async { a + b }.await;
+    assert_eq!(total, sum);
+}
@@ -0,0 +1,48 @@
+#[maybe_async::both]
+async fn parse(s: &str) -> Result<u8, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[maybe_async::test(both, sync = test_log::test, async = tokio::test, fns(parse))]
+#[serial_test::serial]
+async fn test_sync_harness() {
+    assert_eq!(parse("1").await, Ok(1));
+}
+
+#[maybe_async::test(both, async(tokio), fns(parse))]
+#[should_panic(expected = "InvalidDigit")]
+async fn test_should_panic() {
+    parse("x").await.unwrap();
+}
+
+#[maybe_async::test(both, async(tokio))]
+#[ignore]
+async fn test_ignore() {
+    unreachable!();
+}
+
+#[maybe_async::test(
+    both,
+    async(async_std),
+    matrix(tokio, smol),
+    timeout = "5s",
+    fns(parse)
+)]
+async fn test_result() -> Result<(), std::num::ParseIntError> {
+    let n = parse("2").await?;
+    assert_eq!(n, 2);
+    Ok(())
+}
+
+#[maybe_async::test(
+    sync(feature = "is_sync", test_log::test),
+    async(not(feature = "is_sync"), tokio::test)
+)]
+#[serial_test::serial]
+async fn test_sync_condition_harness() -> Result<(), String> {
+    let n = async { 3 }.await;
+    if n != 3 {
+        return Err(format!("{} != 3", n));
+    }
+    Ok(())
+}
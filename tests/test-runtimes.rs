@@ -0,0 +1,75 @@
+#[maybe_async::both]
+async fn double(n: u8) -> u8 {
+    n * 2
+}
+
+#[maybe_async::test(both, async(tokio), timeout = "5s", fns(double))]
+async fn test_tokio() {
+    assert_eq!(double(2).await, 4);
+}
+
+#[maybe_async::test(both, async = tokio(flavor = "multi_thread", worker_threads = 2), fns(double))]
+async fn test_tokio_multi_thread() {
+    assert_eq!(double(3).await, 6);
+}
+
+#[maybe_async::test(both, async(async_std), timeout = "500ms", fns(double))]
+async fn test_async_std() -> Result<(), String> {
+    let n = double(4).await;
+    if n != 8 {
+        return Err(format!("{} != 8", n));
+    }
+    Ok(())
+}
+
+#[maybe_async::test(both, async(smol), timeout = "1s", fns(double))]
+async fn test_smol() {
+    assert_eq!(double(5).await, 10);
+}
+
+#[maybe_async::test(both, async(futures_executor), fns(double))]
+async fn test_futures_executor() {
+    assert_eq!(double(6).await, 12);
+}
+
+#[maybe_async::test(both, async(tokio), timeout = "100ms")]
+#[should_panic(expected = "test timed out after 100ms")]
+async fn test_timeout() {
+    maybe_async::select_mode! {
+        sync => std::thread::sleep(std::time::Duration::from_secs(1)),
+        async => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
+    }
+}
+
+#[maybe_async::test(both, async(tokio), timeout = "5s", fns(double))]
+async fn test_timeout_error_output() -> Result<(), Box<dyn std::error::Error>> {
+    let n = "8".parse::<u8>()?;
+    assert_eq!(double(4).await, n);
+    Ok(())
+}
+
+#[maybe_async::test(
+    feature = "is_sync",
+    async(not(feature = "is_sync"), tokio),
+    timeout = "5s"
+)]
+async fn test_conditions() {
+    let n = async { 1 }.await;
+    assert_eq!(n, 1);
+}
+
+#[maybe_async::test(
+    both,
+    matrix(tokio, async_std, smol, futures_executor),
+    timeout = "5s",
+    fns(double)
+)]
+async fn test_matrix() {
+    assert_eq!(double(7).await, 14);
+}
+
+#[maybe_async::test(feature = "is_sync", matrix(tokio(flavor = "multi_thread"), async_std))]
+async fn test_conditions_matrix() {
+    let n = async { 2 }.await;
+    assert_eq!(n, 2);
+}
@@ -7,9 +7,46 @@ fn ui() {
     t.pass("tests/ui/04-unit-test-util.rs");
     t.pass("tests/ui/05-replace-future-generic-type-with-output.rs");
     t.pass("tests/ui/06-sync_impl_async_impl.rs");
+    t.pass("tests/ui/07-future-output-types.rs");
+    t.pass("tests/ui/08-into-future-output.rs");
+    t.pass("tests/ui/09-try-future-output.rs");
+    t.pass("tests/ui/10-impl-future-argument.rs");
+    t.pass("tests/ui/11-scope-aware-generic-replacement.rs");
+    t.pass("tests/ui/12-associated-future-types.rs");
+    t.pass("tests/ui/13-await-inside-macros.rs");
+    t.pass("tests/ui/14-let-else-and-patterns.rs");
+    t.pass("tests/ui/15-async-closures.rs");
+    t.pass("tests/ui/16-generic-associated-types.rs");
+    t.pass("tests/ui/17-blocking-lint.rs");
+    t.pass("tests/ui/18-mode-only-items.rs");
+    t.pass("tests/ui/19-select-mode.rs");
+    t.pass("tests/ui/20-is-async.rs");
+    t.pass("tests/ui/21-mode-types.rs");
+    t.pass("tests/ui/22-rename-dual-mode-types.rs");
+    t.pass("tests/ui/23-shared-items.rs");
+    t.pass("tests/ui/24-mode-attributes.rs");
+    t.pass("tests/ui/25-async-only-attrs.rs");
+    t.pass("tests/ui/26-doc-attributes.rs");
+    t.pass("tests/ui/27-rename-scope.rs");
+    t.pass("tests/ui/28-local-async-only-names.rs");
+    t.pass("tests/ui/29-timeout-output.rs");
 
     t.compile_fail("tests/ui/test_fail/01-empty-test.rs");
     t.compile_fail("tests/ui/test_fail/02-unknown-path.rs");
     t.compile_fail("tests/ui/test_fail/03-async-gt2.rs");
     t.compile_fail("tests/ui/test_fail/04-bad-sync-cond.rs");
+    t.compile_fail("tests/ui/test_fail/05-future-bound-on-non-generic.rs");
+    t.compile_fail("tests/ui/test_fail/06-async-only-api.rs");
+    t.compile_fail("tests/ui/test_fail/07-blocking-api-warning.rs");
+    t.compile_fail("tests/ui/test_fail/08-blocking-api-strict.rs");
+    t.compile_fail("tests/ui/test_fail/09-select-mode.rs");
+    t.compile_fail("tests/ui/test_fail/10-shared-async-item.rs");
+    t.compile_fail("tests/ui/test_fail/11-bad-mode-attribute.rs");
+    t.compile_fail("tests/ui/test_fail/12-both-test-without-async-macro.rs");
+    t.compile_fail("tests/ui/test_fail/13-duplicate-matrix-runtime.rs");
+    t.compile_fail("tests/ui/test_fail/14-overlapping-test-conditions.rs");
+    t.compile_fail("tests/ui/test_fail/15-case-arguments.rs");
+    t.compile_fail("tests/ui/test_fail/16-bad-doc-attribute.rs");
+    t.compile_fail("tests/ui/test_fail/17-select-mode-duplicate-arm.rs");
+    t.compile_fail("tests/ui/test_fail/18-select-mode-impl-items.rs");
 }
@@ -0,0 +1,41 @@
+#![allow(dead_code, unused_imports)]
+use std::{error::Error, future::Future};
+
+#[maybe_async::must_be_sync]
+async fn with_result<F>(fut: F) -> std::io::Result<()>
+where
+    F: Future<Output = std::io::Result<()>>,
+{
+    fut.await
+}
+
+#[maybe_async::must_be_sync]
+async fn with_ref<'a, F: Future<Output = &'a str>>(fut: F) -> &'a str {
+    fut.await
+}
+
+#[maybe_async::must_be_sync]
+async fn with_tuple<F: Future<Output = (u8, String)>>(fut: F) -> (u8, String) {
+    fut.await
+}
+
+#[maybe_async::must_be_sync]
+async fn with_box<F>(fut: F) -> Result<(), Box<dyn Error>>
+where
+    F: Future<Output = Result<(), Box<dyn Error>>> + Send,
+{
+    fut.await
+}
+
+#[maybe_async::must_be_sync]
+async fn with_unit<F: Future<Output = ()>>(fut: F) {
+    fut.await
+}
+
+fn main() {
+    with_result_sync(Ok(())).unwrap();
+    assert_eq!(with_ref_sync("ref"), "ref");
+    assert_eq!(with_tuple_sync((1, "tuple".to_owned())).0, 1);
+    with_box_sync(Ok(())).unwrap();
+    with_unit_sync(());
+}
@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+
+#[maybe_async::must_be_sync]
+async fn with_into_future<F>(fut: F) -> Vec<u8>
+where
+    F: std::future::IntoFuture<Output = Vec<u8>>,
+{
+    fut.await
+}
+
+#[maybe_async::must_be_sync]
+struct Service;
+
+#[maybe_async::must_be_sync]
+impl Service {
+    async fn call<F: std::future::IntoFuture<Output = u32>>(&self, fut: F) -> u32 {
+        fut.await + 1
+    }
+}
+
+fn main() {
+    assert_eq!(with_into_future_sync(vec![1]), vec![1]);
+    assert_eq!(ServiceSync.call(1), 2);
+}
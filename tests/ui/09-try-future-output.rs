@@ -0,0 +1,23 @@
+#![allow(dead_code, unused_imports)]
+use futures::{TryFuture, TryFutureExt};
+
+#[maybe_async::must_be_sync]
+async fn with_try_future<F>(fut: F) -> Result<u8, String>
+where
+    F: TryFuture<Ok = u8, Error = String>,
+{
+    fut.into_future().await
+}
+
+#[maybe_async::must_be_sync]
+async fn with_try_future_question_mark<F: TryFuture<Ok = u8, Error = String>>(
+    fut: F,
+) -> Result<u8, String> {
+    let res = fut.into_future().await?;
+    Ok(res + 1)
+}
+
+fn main() {
+    assert_eq!(with_try_future_sync(Ok(1)), Ok(1));
+    assert_eq!(with_try_future_question_mark_sync(Ok(1)), Ok(2));
+}
@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+
+#[maybe_async::must_be_sync]
+async fn with_impl_future(
+    fut: impl std::future::Future<Output = std::io::Result<u8>> + Send,
+) -> u8 {
+    fut.await.unwrap()
+}
+
+#[maybe_async::must_be_sync]
+trait Runner {
+    async fn run(&self, fut: impl std::future::Future<Output = String> + Send) -> String {
+        fut.await
+    }
+}
+
+struct Executor;
+
+impl RunnerSync for Executor {}
+
+fn main() {
+    assert_eq!(with_impl_future_sync(Ok(1)), 1);
+    assert_eq!(Executor.run("sync".to_owned()), "sync");
+}
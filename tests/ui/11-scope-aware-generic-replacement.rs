@@ -0,0 +1,54 @@
+#![allow(dead_code, unused_imports)]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[derive(Default)]
+struct Zero;
+
+impl Future for Zero {
+    type Output = u8;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<u8> {
+        Poll::Ready(0)
+    }
+}
+
+#[allow(non_snake_case)]
+mod F {
+    pub fn one() -> u8 {
+        1
+    }
+}
+
+#[maybe_async::must_be_sync]
+async fn with_shadowed_generic<F>(fut: F) -> u8
+where
+    F: Future<Output = u8>,
+    F::Output: Copy,
+{
+    // a module path is not a use of the generic parameter
+    let one = crate::F::one();
+
+    // nested items declare their own `F`
+    fn nested<F: Default>() -> F {
+        F::default()
+    }
+    let zero: u8 = nested();
+
+    let output: F::Output = fut.await;
+    let qualified: <F as Future>::Output = output;
+    qualified + one + zero
+}
+
+#[maybe_async::must_be_sync]
+async fn with_associated_fn<F: Future<Output = u8> + Default>() -> u8 {
+    F::default().await
+}
+
+fn main() {
+    assert_eq!(with_shadowed_generic_sync(1), 2);
+    assert_eq!(with_associated_fn_sync(), 0);
+}
@@ -0,0 +1,65 @@
+#![allow(dead_code, unused_imports)]
+use futures::{
+    future::{BoxFuture, FutureExt},
+    stream::{BoxStream, Stream},
+};
+use std::{future::Future, pin::Pin};
+
+#[maybe_async::must_be_sync]
+trait Service {
+    type Item;
+    type Fut: Future<Output = Self::Item>;
+    type Other: Future<Output = Self::Item> + Send;
+
+    fn call(&self) -> Self::Fut;
+
+    fn call_other(&self) -> Self::Other;
+
+    fn call_default(&self) -> Self::Fut {
+        self.call()
+    }
+}
+
+#[maybe_async::must_be_sync]
+trait Source {
+    type Items: Stream<Item = u8>;
+
+    fn items(&self) -> Self::Items;
+}
+
+#[maybe_async::must_be_sync]
+struct Doubler(u8);
+
+#[maybe_async::must_be_sync(types(Service))]
+impl Service for Doubler {
+    type Item = u8;
+    type Fut = Pin<Box<dyn Future<Output = u8> + Send>>;
+    type Other = BoxFuture<'static, u8>;
+
+    fn call(&self) -> Self::Fut {
+        let value = self.0;
+        Box::pin(async move { value * 2 })
+    }
+
+    fn call_other(&self) -> Self::Other {
+        let value = self.0;
+        async move { value + 1 }.boxed()
+    }
+}
+
+#[maybe_async::must_be_sync(types(Source))]
+impl Source for Doubler {
+    type Items = BoxStream<'static, u8>;
+
+    fn items(&self) -> Self::Items {
+        Box::new(vec![self.0, self.0 * 2].into_iter())
+    }
+}
+
+fn main() {
+    let doubler = DoublerSync(2);
+    assert_eq!(ServiceSync::call(&doubler), 4);
+    assert_eq!(ServiceSync::call_other(&doubler), 3);
+    assert_eq!(ServiceSync::call_default(&doubler), 4);
+    assert_eq!(SourceSync::items(&doubler).sum::<u8>(), 6);
+}
@@ -0,0 +1,42 @@
+#![allow(dead_code)]
+
+mod log {
+    macro_rules! info {
+        (target: $target:expr, $($arg:tt)+) => {
+            format!("{}: {}", $target, format!($($arg)+))
+        };
+        ($($arg:tt)+) => {
+            format!($($arg)+)
+        };
+    }
+    pub(crate) use info;
+}
+
+#[maybe_async::must_be_sync]
+struct Client;
+
+#[maybe_async::must_be_sync(macros(log::info))]
+impl Client {
+    async fn get(&self) -> u8 {
+        1
+    }
+
+    async fn with_macros(&self) -> String {
+        use std::fmt::Write;
+
+        assert_eq!(self.get().await, 1);
+        assert!(self.get().await > 0, "{} should be positive", self.get().await);
+        debug_assert_ne!(self.get().await, 0);
+        let v = vec![self.get().await; 2];
+        let mut out = format!("{}-{}", self.get().await, v.len());
+        write!(out, "{value}", value = self.get().await).unwrap();
+        println!("{}", async { self.get().await }.await);
+        out.push_str(&log::info!("{}", self.get().await));
+        out.push_str(&log::info!(target: "app", "{}", self.get().await));
+        out
+    }
+}
+
+fn main() {
+    assert_eq!(ClientSync.with_macros(), "1-211app: 1");
+}
@@ -0,0 +1,28 @@
+#![allow(dead_code)]
+
+#[maybe_async::must_be_sync]
+struct Parser;
+
+#[maybe_async::must_be_sync]
+impl Parser {
+    async fn next(&self, input: &str) -> Option<u8> {
+        input.parse().ok()
+    }
+
+    async fn parse(&self, input: &str) -> u8 {
+        let Some(value) = self.next(input).await else {
+            return 0;
+        };
+        match (value, self.next("1").await) {
+            (0 | 1, _) => value,
+            (2..=9, Some(factor @ 1..)) => value * (factor + 1),
+            _ => value,
+        }
+    }
+}
+
+fn main() {
+    assert_eq!(ParserSync.parse("x"), 0);
+    assert_eq!(ParserSync.parse("1"), 1);
+    assert_eq!(ParserSync.parse("3"), 6);
+}
@@ -0,0 +1,30 @@
+#![allow(dead_code)]
+
+#[maybe_async::must_be_sync]
+struct Runner;
+
+#[maybe_async::must_be_sync]
+impl Runner {
+    async fn one(&self) -> u8 {
+        1
+    }
+
+    async fn run<F: AsyncFn(u8) -> u8>(&self, f: F) -> u8 {
+        f(self.one().await).await
+    }
+
+    async fn closures(&self) -> u8 {
+        let add = async |x: u8| x + self.one().await;
+        let offset = 2;
+        let add_offset = async move |x: u8| {
+            let y = x + offset;
+            y
+        };
+        add_offset(add(1).await).await
+    }
+}
+
+fn main() {
+    assert_eq!(RunnerSync.run(|x| x + 1), 2);
+    assert_eq!(RunnerSync.closures(), 4);
+}
@@ -0,0 +1,28 @@
+#![allow(dead_code)]
+
+#[maybe_async::must_be_sync]
+trait Lender {
+    type Item<'a>
+    where
+        Self: 'a;
+
+    async fn lend<'a>(&'a mut self) -> Self::Item<'a>;
+}
+
+#[maybe_async::must_be_sync]
+struct Buffer(Vec<u8>);
+
+#[maybe_async::must_be_sync(types(Lender))]
+impl Lender for Buffer {
+    type Item<'a> = &'a mut [u8] where Self: 'a;
+
+    async fn lend<'a>(&'a mut self) -> Self::Item<'a> {
+        &mut self.0
+    }
+}
+
+fn main() {
+    let mut buffer = BufferSync(vec![1]);
+    buffer.lend()[0] = 2;
+    assert_eq!(buffer.0, vec![2]);
+}
@@ -0,0 +1,41 @@
+// no false positives, or the shims would fail the build
+#![deny(deprecated)]
+
+mod fs {
+    pub async fn read_to_string(path: &str) -> std::io::Result<String> {
+        std::future::ready(std::fs::read_to_string(path)).await
+    }
+}
+
+#[maybe_async::must_be_async(blocking(my_runtime::block_on))]
+struct Counter(usize);
+
+#[maybe_async::must_be_async(blocking(my_runtime::block_on))]
+impl Counter {
+    async fn add(&mut self, n: usize) -> usize {
+        self.0 += n;
+        self.0
+    }
+}
+
+#[maybe_async::must_be_async(blocking)]
+async fn load_len() -> usize {
+    // an awaited call is not blocking, like `tokio::fs::read_to_string`
+    let content = fs::read_to_string(file!()).await.unwrap();
+    content.len()
+}
+
+#[maybe_async::must_be_async(strict)]
+async fn read_len() -> usize {
+    // blocking code is fine in `spawn_blocking`
+    tokio::task::spawn_blocking(|| std::fs::read(file!()).unwrap().len())
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+async fn main() {
+    let mut counter = CounterAsync(0);
+    assert_eq!(counter.add(2).await, 2);
+    assert_eq!(load_len_async().await, read_len_async().await);
+}
@@ -0,0 +1,106 @@
+#[maybe_async::both]
+pub struct Pages {
+    pages: Vec<u32>,
+    #[sync_only]
+    cursor: usize,
+    #[maybe_async::async_only]
+    pending: Option<u32>,
+}
+
+#[maybe_async::both]
+pub enum Event {
+    Page(u32),
+    #[async_only]
+    Pending,
+}
+
+#[maybe_async::both]
+pub trait Source {
+    async fn next(&mut self) -> Option<u32>;
+
+    #[sync_only]
+    fn remaining(&self) -> usize;
+
+    #[async_only]
+    fn is_pending(&self) -> bool;
+}
+
+#[maybe_async::both]
+impl Pages {
+    async fn fetch(&mut self) -> Option<u32> {
+        #[sync_only]
+        use std::convert::identity as load;
+        #[async_only]
+        use std::future::ready as load;
+
+        #[sync_only]
+        let page = self.pages.get(self.cursor).copied();
+        #[async_only]
+        let page = self.pending.take().or_else(|| self.pages.first().copied());
+        #[sync_only]
+        {
+            self.cursor += 1;
+        }
+        load(page).await
+    }
+
+    #[sync_only]
+    fn into_iter(self) -> std::vec::IntoIter<u32> {
+        self.pages.into_iter()
+    }
+
+    #[async_only]
+    fn into_stream(self) -> Vec<EventAsync> {
+        let mut events = vec![EventAsync::Pending];
+        events.extend(self.pages.into_iter().map(EventAsync::Page));
+        events
+    }
+}
+
+#[maybe_async::sync_impl]
+impl SourceSync for Pages {
+    fn next(&mut self) -> Option<u32> {
+        self.fetch()
+    }
+
+    fn remaining(&self) -> usize {
+        self.pages.len() - self.cursor
+    }
+}
+
+#[maybe_async::async_impl]
+impl SourceAsync for Pages {
+    async fn next(&mut self) -> Option<u32> {
+        self.fetch().await
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        let mut pages = PagesSync {
+            pages: vec![1, 2],
+            cursor: 0,
+        };
+        assert_eq!(pages.next(), Some(1));
+        assert_eq!(pages.remaining(), 1);
+        assert_eq!(pages.into_iter().sum::<u32>(), 3);
+        let EventSync::Page(page) = EventSync::Page(1);
+        assert_eq!(page, 1);
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        let mut pages = PagesAsync {
+            pages: vec![1, 2],
+            pending: Some(0),
+        };
+        assert!(pages.is_pending());
+        assert_eq!(pages.next().await, Some(0));
+        assert_eq!(pages.next().await, Some(1));
+        assert_eq!(pages.into_stream().len(), 3);
+    });
+}
@@ -0,0 +1,79 @@
+#[maybe_async::both]
+pub struct Client {
+    retries: u32,
+}
+
+#[maybe_async::both]
+impl Client {
+    async fn pause(&self, d: std::time::Duration) {
+        maybe_async::select_mode! {
+            sync => std::thread::sleep(d),
+            async => async_std::task::sleep(d).await,
+        }
+    }
+
+    async fn name(&self) -> String {
+        // statements in braces share the scope of the function
+        maybe_async::select_mode! {
+            sync => { let name = stringify!(ClientSync); },
+            async => { let name = stringify!(ClientAsync); },
+        }
+        self.pause(std::time::Duration::from_millis(1)).await;
+        name.to_string()
+    }
+
+    async fn budget(&self) -> u32 {
+        // the arm keeps its precedence
+        let extra = maybe_async::select_mode!(sync => self.retries + 1, async => self.retries + 2);
+        2 * maybe_async::select_mode!(sync => extra - 1, async => extra - 2)
+    }
+
+    async fn log(&self, events: &mut Vec<&'static str>) {
+        maybe_async::select_mode!(async => events.push("yield"));
+        events.push("log");
+    }
+
+    // inside the arguments of other macros
+    async fn label(&self) -> String {
+        format!("{}-{}", self.retries, maybe_async::select_mode!(sync => "sync", async => "async"))
+    }
+
+    // the other arm is never type checked
+    maybe_async::select_mode! {
+        sync => {
+            fn twin(&self) -> ClientSync {
+                ClientSync { retries: self.retries }
+            }
+        },
+        async => {
+            fn twin(&self) -> ClientAsync {
+                ClientAsync { retries: self.retries }
+            }
+        },
+    }
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        let client = ClientSync { retries: 1 };
+        assert_eq!(client.name(), "ClientSync");
+        assert_eq!(client.budget(), 2);
+        let mut events = vec![];
+        client.log(&mut events);
+        assert_eq!(events, ["log"]);
+        assert_eq!(client.label(), "1-sync");
+        assert_eq!(client.twin().retries, 1);
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        let client = ClientAsync { retries: 1 };
+        assert_eq!(client.name().await, "ClientAsync");
+        assert_eq!(client.budget().await, 2);
+        let mut events = vec![];
+        client.log(&mut events).await;
+        assert_eq!(events, ["yield", "log"]);
+        assert_eq!(client.label().await, "1-async");
+        assert_eq!(client.twin().retries, 1);
+    });
+}
@@ -0,0 +1,44 @@
+#[maybe_async::both]
+pub struct Client<T: Clone> {
+    name: T,
+}
+
+#[maybe_async::both]
+pub enum Mode {
+    Sync,
+    Async,
+}
+
+#[maybe_async::both]
+impl<T: Clone + std::fmt::Display> Client<T> {
+    async fn label(&self) -> String {
+        format!("{}-{}", self.name, maybe_async::is_async!())
+    }
+
+    async fn kind(&self) -> &'static str {
+        if maybe_async::is_async!() {
+            "async"
+        } else {
+            "sync"
+        }
+    }
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        const { assert!(!ClientSync::<u8>::IS_ASYNC) };
+        assert!(!ModeSync::IS_ASYNC);
+        let client = ClientSync { name: "client" };
+        assert_eq!(client.label(), "client-false");
+        assert_eq!(client.kind(), "sync");
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        const { assert!(ClientAsync::<u8>::IS_ASYNC) };
+        assert!(ModeAsync::IS_ASYNC);
+        let client = ClientAsync { name: "client" };
+        assert_eq!(client.label().await, "client-true");
+        assert_eq!(client.kind().await, "async");
+    });
+}
@@ -0,0 +1,63 @@
+#[maybe_async::both]
+#[sync_type(std::sync::MutexGuard<'a, u32>)]
+#[async_type(futures::lock::MutexGuard<'a, u32>)]
+type Guard<'a> = ();
+
+#[maybe_async::both]
+pub struct Counter {
+    #[sync_type(std::sync::Mutex<u32>)]
+    #[async_type(futures::lock::Mutex<u32>)]
+    count: _,
+    // the declared type is kept by the version without an attribute
+    #[maybe_async::sync_type(u64)]
+    total: u32,
+}
+
+#[maybe_async::both]
+pub enum Reply {
+    Count(
+        #[sync_type(std::sync::Arc<std::sync::Mutex<u32>>)]
+        #[async_type(std::sync::Arc<futures::lock::Mutex<u32>>)]
+        _,
+    ),
+    Done,
+}
+
+#[maybe_async::both]
+impl Counter {
+    async fn increase(&self) -> u32 {
+        maybe_async::select_mode! {
+            sync => { let mut count: GuardSync = self.count.lock().unwrap(); },
+            async => { let mut count: GuardAsync = self.count.lock().await; },
+        }
+        *count += 1;
+        *count
+    }
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        let counter = CounterSync {
+            count: std::sync::Mutex::new(0),
+            total: 0u64,
+        };
+        assert_eq!(counter.increase(), 1);
+        let ReplySync::Count(count) = ReplySync::Count(Default::default()) else {
+            unreachable!()
+        };
+        assert_eq!(*count.lock().unwrap(), 0);
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        let counter = CounterAsync {
+            count: futures::lock::Mutex::new(0),
+            total: 0u32,
+        };
+        assert_eq!(counter.increase().await, 1);
+        let ReplyAsync::Count(count) = ReplyAsync::Count(Default::default()) else {
+            unreachable!()
+        };
+        assert_eq!(*count.lock().await, 0);
+    });
+}
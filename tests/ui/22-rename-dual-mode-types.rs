@@ -0,0 +1,69 @@
+#[maybe_async::must_be_sync]
+pub struct Config {
+    retries: u32,
+}
+
+#[maybe_async::must_be_sync]
+pub trait Transport {
+    async fn send(&self, body: &str) -> usize;
+}
+
+#[maybe_async::must_be_sync(types(Transport))]
+pub trait Api: Transport {
+    async fn ping(&self) -> usize {
+        self.send("ping").await
+    }
+}
+
+#[maybe_async::must_be_sync]
+pub struct Inner;
+
+#[maybe_async::must_be_sync(types(Transport))]
+impl Transport for Inner {
+    async fn send(&self, body: &str) -> usize {
+        body.len()
+    }
+}
+
+#[maybe_async::must_be_sync(types(Api))]
+impl Api for Inner {}
+
+#[maybe_async::must_be_sync(types(Inner, Transport))]
+pub struct Client<T: Transport> {
+    inner: Inner,
+    transport: T,
+    fallback: Option<Box<dyn Transport + Send + Sync>>,
+}
+
+#[maybe_async::must_be_sync(types(Config))]
+impl From<Config> for Inner {
+    fn from(_config: Config) -> Self {
+        Inner
+    }
+}
+
+#[maybe_async::must_be_sync(types(Inner, Transport, Config))]
+impl<T: Transport> Client<T> {
+    fn new(transport: T, config: Config) -> Client<T> {
+        Client {
+            inner: Inner::from(config),
+            transport,
+            fallback: Some(Box::new(Inner)),
+        }
+    }
+
+    async fn send_all(&self, body: &str, extra: &impl Transport) -> usize {
+        let fallback: &dyn Transport = self.fallback.as_deref().unwrap();
+        self.inner.send(body).await
+            + self.transport.send(body).await
+            + fallback.send(body).await
+            + extra.send(body).await
+    }
+}
+
+fn main() {
+    let inner = InnerSync;
+    let client = ClientSync::new(InnerSync, ConfigSync { retries: 1 });
+    assert_eq!(client.send_all("ab", &inner), 8);
+    assert_eq!(inner.ping(), 4);
+}
@@ -0,0 +1,61 @@
+#[maybe_async::both(shared)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    retries: u32,
+}
+
+#[maybe_async::both(shared)]
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Timeout(Config),
+}
+
+#[maybe_async::both(shared)]
+impl Config {
+    fn new(retries: u32) -> Config {
+        Config { retries }
+    }
+}
+
+#[maybe_async::both(shared)]
+fn fail(config: &Config) -> Result<u32, Error> {
+    Err(Error::Timeout(config.clone()))
+}
+
+#[maybe_async::both]
+pub struct Client {
+    config: Config,
+}
+
+// without `shared`, a function has a copy per version even when it is not
+// `async`
+#[maybe_async::both(types(Client))]
+fn config(client: &Client) -> &Config {
+    &client.config
+}
+
+#[maybe_async::both]
+impl Client {
+    async fn request(&self) -> Result<u32, Error> {
+        async { fail(&self.config) }.await
+    }
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        let client = ClientSync {
+            config: Config::new(1),
+        };
+        assert_eq!(client.request(), Err(Error::Timeout(Config::new(1))));
+        assert_eq!(config_sync(&client), &Config::new(1));
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        let client = ClientAsync {
+            config: Config::new(1),
+        };
+        assert_eq!(client.request().await, Err(Error::Timeout(Config::new(1))));
+        assert_eq!(config_async(&client), &Config::new(1));
+    });
+}
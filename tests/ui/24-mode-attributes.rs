@@ -0,0 +1,43 @@
+#[maybe_async::both]
+#[sync_attr(derive(Clone, Debug, PartialEq))]
+#[maybe_async::async_attr(derive(Default))]
+pub struct Client {
+    #[async_attr(allow(dead_code))]
+    name: String,
+}
+
+#[maybe_async::both]
+#[derive(Debug, Default, PartialEq)]
+pub enum Mode {
+    #[sync_attr(default)]
+    Blocking,
+    #[async_attr(default)]
+    NonBlocking,
+}
+
+#[maybe_async::both]
+impl Client {
+    #[sync_attr(inline, must_use)]
+    #[async_attr(must_use = "futures do nothing unless awaited")]
+    async fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        let client = ClientSync {
+            name: "client".into(),
+        };
+        assert_eq!(client.clone(), client);
+        assert_eq!(client.name(), "client");
+        assert_eq!(ModeSync::default(), ModeSync::Blocking);
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        let client = ClientAsync::default();
+        assert_eq!(client.name().await, "");
+        assert_eq!(ModeAsync::default(), ModeAsync::NonBlocking);
+    });
+}
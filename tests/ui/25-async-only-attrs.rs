@@ -0,0 +1,31 @@
+#![deny(unused_must_use)]
+
+#[maybe_async::must_be_sync]
+#[async_trait::async_trait]
+pub trait Service {
+    async fn call(&self) -> u8;
+}
+
+#[maybe_async::must_be_sync]
+pub struct Echo;
+
+#[maybe_async::must_be_sync(types(Service))]
+#[async_trait::async_trait]
+#[async_trait::async_trait]
+impl Service for Echo {
+    async fn call(&self) -> u8 {
+        1
+    }
+}
+
+// runtime attributes and the ones listed in `async_only_attrs` are removed
+#[maybe_async::must_be_sync(types(Echo, Service), async_only_attrs(must_use))]
+#[must_use]
+#[async_std::test]
+async fn test_call() -> u8 {
+    Service::call(&Echo).await
+}
+
+fn main() {
+    test_call_sync();
+}
@@ -0,0 +1,35 @@
+#[maybe_async::both]
+/// A client.
+#[doc_sync = " Blocks the current thread."]
+#[maybe_async::doc_async = " Needs an async runtime."]
+pub struct Client {
+    #[doc_sync = " Read timeout."]
+    pub timeout: u64,
+}
+
+#[maybe_async::both]
+impl Client {
+    #[doc_async = " Resolves once the response is read."]
+    pub async fn get(&self) -> u64 {
+        self.timeout
+    }
+}
+
+#[maybe_async::both]
+#[maybe_async::doc_sync = " Blocks until `n` is ready."]
+pub async fn ready(n: u64) -> u64 {
+    n
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    {
+        assert_eq!(ClientSync { timeout: 1 }.get(), 1);
+        assert_eq!(ready_sync(2), 2);
+    }
+    #[cfg(feature = "is_async")]
+    futures::executor::block_on(async {
+        assert_eq!(ClientAsync { timeout: 1 }.get().await, 1);
+        assert_eq!(ready_async(2).await, 2);
+    });
+}
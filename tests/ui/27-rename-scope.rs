@@ -0,0 +1,35 @@
+mod other {
+    pub struct Inner;
+}
+
+#[derive(PartialEq)]
+pub enum Role {
+    Client,
+    Server,
+}
+
+#[maybe_async::must_be_sync]
+pub struct Inner;
+
+#[maybe_async::must_be_sync]
+pub struct Client {
+    role: Role,
+}
+
+// `Role::Client` is a variant and `other::Inner` a type of another module,
+// which keep their names
+#[maybe_async::must_be_sync(types(Inner))]
+impl Client {
+    async fn new(_inner: Inner, _other: other::Inner) -> Self {
+        Self { role: Role::Client }
+    }
+
+    async fn is_client(&self) -> bool {
+        self.role == Role::Client && self.role != Role::Server
+    }
+}
+
+fn main() {
+    let client = ClientSync::new(InnerSync, other::Inner);
+    assert!(client.is_client());
+}
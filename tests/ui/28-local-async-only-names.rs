@@ -0,0 +1,19 @@
+// a local macro named like an async only one is not reported
+macro_rules! select {
+    ($a:expr, $b:expr) => {
+        if $a > $b {
+            $a
+        } else {
+            $b
+        }
+    };
+}
+
+#[maybe_async::must_be_sync]
+async fn largest(a: u8, b: u8) -> u8 {
+    select!(a, b)
+}
+
+fn main() {
+    assert_eq!(largest_sync(1, 2), 2);
+}
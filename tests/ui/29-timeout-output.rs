@@ -0,0 +1,12 @@
+// the sync version runs on the calling thread, its output is not sent across
+// threads
+#[maybe_async::test(both, sync = inline, async(tokio), timeout = "5s")]
+async fn parse() -> Result<u8, Box<dyn std::error::Error>> {
+    let n = std::rc::Rc::new("4".parse::<u8>()?);
+    Ok(*n)
+}
+
+fn main() {
+    #[cfg(feature = "is_sync")]
+    assert_eq!(parse_sync().unwrap(), 4);
+}
@@ -0,0 +1,14 @@
+use std::future::Future;
+
+// only generic type parameters can be replaced with their output type
+#[maybe_async::must_be_sync]
+async fn with_ref_bound<'a, F>(fut: &'a mut F) -> u8
+where
+    &'a mut F: Future<Output = u8>,
+{
+    fut.await
+}
+
+fn main() {
+
+}
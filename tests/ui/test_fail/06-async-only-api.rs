@@ -0,0 +1,18 @@
+#![allow(unused_imports)]
+use std::time::Duration;
+
+// async only APIs can not be converted to sync code
+#[maybe_async::must_be_sync]
+async fn spawn_and_wait() -> u8 {
+    let handle = tokio::spawn(async { 1 });
+    let value = tokio::time::timeout(Duration::from_secs(1), async { 2 }).await;
+    tokio::select! {
+        _ = async {} => {}
+    }
+    handle.await.unwrap() + value.unwrap()
+}
+
+fn main() {
+    // the converted function is still emitted, only the APIs are reported
+    spawn_and_wait();
+}
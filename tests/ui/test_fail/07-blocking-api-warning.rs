@@ -0,0 +1,18 @@
+#![deny(deprecated)]
+use std::{thread, time::Duration};
+
+// blocking APIs are reported as deprecation warnings in the async version
+#[maybe_async::must_be_async(blocking(my_runtime::block_on))]
+async fn load() -> usize {
+    thread::sleep(Duration::from_millis(1));
+    my_runtime::block_on(async {});
+    std::fs::read("Cargo.toml").unwrap().len()
+}
+
+mod my_runtime {
+    pub fn block_on<F: std::future::Future>(_: F) {}
+}
+
+fn main() {
+
+}
@@ -0,0 +1,10 @@
+// blocking APIs are compile errors in the async version with `strict`
+#[maybe_async::must_be_async(strict)]
+async fn fetch() -> String {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+    reqwest::blocking::get("https://example.com").unwrap().text().unwrap()
+}
+
+fn main() {
+
+}
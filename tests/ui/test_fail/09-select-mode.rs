@@ -0,0 +1,8 @@
+// `select_mode!` is only resolved inside converted items
+fn outside() -> u8 {
+    maybe_async::select_mode!(sync => 1, async => 2)
+}
+
+fn main() {
+
+}
@@ -0,0 +1,15 @@
+// `shared` items can not have anything to convert
+#[maybe_async::both(shared)]
+pub struct Config {
+    #[sync_type(u64)]
+    retries: u32,
+}
+
+#[maybe_async::both(shared)]
+async fn fetch() -> u32 {
+    1
+}
+
+fn main() {
+
+}
@@ -0,0 +1,8 @@
+// the arguments of `sync_attr` must be attributes
+#[maybe_async::must_be_sync]
+#[sync_attr(= "client")]
+pub struct Client;
+
+fn main() {
+
+}
@@ -0,0 +1,11 @@
+#[maybe_async::both]
+async fn answer() -> u8 {
+    42
+}
+
+#[maybe_async::test(both, matrix(tokio, tokio(flavor = "multi_thread")))]
+async fn test_answer() {
+    assert_eq!(answer().await, 42);
+}
+
+fn main() {}
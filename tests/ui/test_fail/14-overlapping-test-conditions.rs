@@ -0,0 +1,11 @@
+#[maybe_async::test(
+    not(miri),
+    async(not(doc), tokio::test),
+    async(not(doctest), async_std::test)
+)]
+async fn test_answer() {
+    let answer = async { 42 }.await;
+    assert_eq!(answer, 42);
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#[maybe_async::both]
+async fn double(n: u8) -> u8 {
+    n * 2
+}
+
+#[maybe_async::test(both, async(tokio))]
+#[case(1, 2)]
+#[case(2)]
+async fn test_double(n: u8, expected: u8) {
+    assert_eq!(double(n).await, expected);
+}
+
+fn main() {}
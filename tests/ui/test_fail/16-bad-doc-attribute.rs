@@ -0,0 +1,8 @@
+// `doc_sync` takes the text of the docs, like `doc`
+#[maybe_async::must_be_sync]
+#[doc_sync("a blocking client")]
+pub struct Client;
+
+fn main() {
+
+}
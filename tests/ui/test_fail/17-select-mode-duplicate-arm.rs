@@ -0,0 +1,9 @@
+// each version has at most one arm
+#[maybe_async::must_be_sync]
+async fn duplicate_arm() -> u8 {
+    maybe_async::select_mode!(sync => 1, sync => 2)
+}
+
+fn main() {
+
+}
@@ -0,0 +1,12 @@
+// inside an impl, the arms hold items
+#[maybe_async::must_be_sync]
+struct Foo;
+
+#[maybe_async::must_be_sync]
+impl Foo {
+    maybe_async::select_mode!(sync => 1);
+}
+
+fn main() {
+
+}